@@ -0,0 +1,443 @@
+//! `c30dao`: command-line admin tool for the dao-voting program.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anchor_lang::{AccountDeserialize, Discriminator, Space};
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use dao_voting_client::dao_voting::{
+    self, pda, quote, GlobalState, ProposalConfig, TokenMetadata, UserVault, Vault, VaultMode,
+    GLOBAL_STATE_VERSION, USER_VAULT_VERSION, VAULT_VERSION,
+};
+use dao_voting_client::{
+    CreateStAAndVaultBuilder, DepositProjectTokensBuilder, InitializeBuilder,
+    MigrateGlobalStateBuilder, MigrateUserVaultBuilder, MigrateVaultBuilder, PauseBuilder,
+    SetProjectTokenBuilder,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+
+#[derive(Parser)]
+#[command(name = "c30dao", version, about = "Operate the C30DAO voting program")]
+struct Cli {
+    /// JSON RPC endpoint, e.g. a local validator or a cluster URL.
+    #[arg(
+        long,
+        short = 'u',
+        global = true,
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+    /// Keypair file used to sign and pay for transactions.
+    #[arg(
+        long,
+        short = 'k',
+        global = true,
+        default_value = "~/.config/solana/id.json"
+    )]
+    keypair: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the global state and the governance token mints.
+    Init(InitArgs),
+    /// Create a vault and its vote token mint.
+    CreateVault(CreateVaultArgs),
+    /// Set the project token a vault pays out and when claims open.
+    SetProjectToken {
+        #[arg(long)]
+        vault_id: u64,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        convert_time: i64,
+        /// Sign as an Operator role holder rather than the admin.
+        #[arg(long)]
+        with_role: bool,
+    },
+    /// Deposit project tokens from the signer's token account into a vault.
+    Deposit {
+        #[arg(long)]
+        vault_id: u64,
+        #[arg(long)]
+        amount: u64,
+        /// Sign as a ProjectPartner role holder rather than the admin.
+        #[arg(long)]
+        with_role: bool,
+    },
+    /// Pause conversions, voting, deposits and claims.
+    Pause {
+        /// Sign as a Pauser role holder rather than the admin.
+        #[arg(long)]
+        with_role: bool,
+    },
+    /// Migrate the global state, every vault and every user vault to the
+    /// current account layout.
+    Migrate,
+    /// Print every vault.
+    ListVaults,
+    /// Print a single vault.
+    ShowVault { vault_id: u64 },
+    /// Print a user's position in a vault. Defaults to the signer.
+    ShowUser { vault_id: u64, user: Option<Pubkey> },
+}
+
+#[derive(Args)]
+struct InitArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    symbol: String,
+    #[arg(long)]
+    uri: String,
+    #[arg(long)]
+    st_name: String,
+    #[arg(long)]
+    st_symbol: String,
+    #[arg(long)]
+    st_uri: String,
+    /// Seconds queued admin actions wait before they can execute.
+    #[arg(long, default_value_t = 2 * 24 * 60 * 60)]
+    timelock_delay: i64,
+    /// Seconds proposals stay open for voting.
+    #[arg(long, default_value_t = 3 * 24 * 60 * 60)]
+    voting_period: i64,
+    #[arg(long)]
+    quorum: u64,
+    #[arg(long, default_value_t = 5_000)]
+    threshold_bps: u16,
+}
+
+#[derive(Args)]
+struct CreateVaultArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    symbol: String,
+    #[arg(long)]
+    uri: String,
+    #[arg(long)]
+    max_vote_cap: u128,
+    #[arg(long)]
+    voting_start: i64,
+    #[arg(long)]
+    deadline: i64,
+    #[arg(long)]
+    max_per_user: Option<u128>,
+    #[arg(long)]
+    min_per_vote: Option<u64>,
+    #[arg(long, default_value_t = 0)]
+    max_deadline_extension: i64,
+    /// Escrow st_governance until the deadline instead of burning it.
+    #[arg(long)]
+    lock_mode: bool,
+    /// Sign as an Operator role holder rather than the admin.
+    #[arg(long)]
+    with_role: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+    // Read-only commands work without a keypair
+    let load_signer = || {
+        let path = expand_home(&cli.keypair);
+        read_keypair_file(&path)
+            .map_err(|err| anyhow!("failed to read keypair {}: {err}", path.display()))
+    };
+
+    match cli.command {
+        Command::Init(args) => {
+            let signer = load_signer()?;
+            let ix = InitializeBuilder::new(
+                signer.pubkey(),
+                TokenMetadata {
+                    name: args.name,
+                    symbol: args.symbol,
+                    uri: args.uri,
+                },
+                TokenMetadata {
+                    name: args.st_name,
+                    symbol: args.st_symbol,
+                    uri: args.st_uri,
+                },
+                args.timelock_delay,
+                ProposalConfig {
+                    voting_period: args.voting_period,
+                    quorum: args.quorum,
+                    threshold_bps: args.threshold_bps,
+                },
+            )
+            .instruction();
+            println!("Initialized: {}", send(&rpc, &signer, ix)?);
+        }
+        Command::CreateVault(args) => {
+            let signer = load_signer()?;
+            let global_state: GlobalState = fetch(&rpc, &pda::find_global_state_address().0)?;
+            let vault_id = global_state.vault_count;
+            let mut builder = CreateStAAndVaultBuilder::new(
+                signer.pubkey(),
+                vault_id,
+                TokenMetadata {
+                    name: args.name,
+                    symbol: args.symbol,
+                    uri: args.uri,
+                },
+                args.max_vote_cap,
+                args.voting_start,
+                args.deadline,
+            )
+            .max_deadline_extension(args.max_deadline_extension);
+            if let Some(max_per_user) = args.max_per_user {
+                builder = builder.max_per_user(max_per_user);
+            }
+            if let Some(min_per_vote) = args.min_per_vote {
+                builder = builder.min_per_vote(min_per_vote);
+            }
+            if args.lock_mode {
+                builder = builder.mode(VaultMode::Lock);
+            }
+            if args.with_role {
+                builder = builder.with_role();
+            }
+            let signature = send(&rpc, &signer, builder.instruction())?;
+            println!("Created vault {vault_id}: {signature}");
+        }
+        Command::SetProjectToken {
+            vault_id,
+            mint,
+            convert_time,
+            with_role,
+        } => {
+            let signer = load_signer()?;
+            let mut builder =
+                SetProjectTokenBuilder::new(signer.pubkey(), vault_id, mint, convert_time);
+            if with_role {
+                builder = builder.with_role();
+            }
+            println!(
+                "Set project token: {}",
+                send(&rpc, &signer, builder.instruction())?
+            );
+        }
+        Command::Deposit {
+            vault_id,
+            amount,
+            with_role,
+        } => {
+            let signer = load_signer()?;
+            let vault: Vault = fetch(&rpc, &pda::find_vault_address(vault_id).0)?;
+            let mut builder = DepositProjectTokensBuilder::new(
+                signer.pubkey(),
+                vault_id,
+                vault.project_token_mint,
+                amount,
+            );
+            if with_role {
+                builder = builder.with_role();
+            }
+            println!("Deposited: {}", send(&rpc, &signer, builder.instruction())?);
+        }
+        Command::Pause { with_role } => {
+            let signer = load_signer()?;
+            let mut builder = PauseBuilder::new(signer.pubkey());
+            if with_role {
+                builder = builder.with_role();
+            }
+            println!("Paused: {}", send(&rpc, &signer, builder.instruction())?);
+        }
+        Command::Migrate => {
+            let signer = load_signer()?;
+            // Vaults are counted from the global state, so it goes first
+            let address = pda::find_global_state_address().0;
+            if outdated(
+                &rpc,
+                &address,
+                GLOBAL_STATE_VERSION,
+                |state: &GlobalState| state.version,
+            )? {
+                let ix = MigrateGlobalStateBuilder::new(signer.pubkey()).instruction();
+                println!("Migrated global state: {}", send(&rpc, &signer, ix)?);
+            }
+            let global_state: GlobalState = fetch(&rpc, &address)?;
+            let mut vault_ids = HashMap::new();
+            for vault_id in 0..global_state.vault_count {
+                let address = pda::find_vault_address(vault_id).0;
+                vault_ids.insert(address, vault_id);
+                if outdated(&rpc, &address, VAULT_VERSION, |vault: &Vault| vault.version)? {
+                    let ix = MigrateVaultBuilder::new(signer.pubkey(), vault_id).instruction();
+                    println!("Migrated vault {vault_id}: {}", send(&rpc, &signer, ix)?);
+                }
+            }
+
+            // User vaults aren't counted anywhere, so look them up by type.
+            // `user` and `vault` lead every layout, so they read the same
+            // before and after migrating.
+            let discriminator = Memcmp::new_raw_bytes(0, UserVault::DISCRIMINATOR.to_vec());
+            let user_vaults = rpc.get_program_accounts_with_config(
+                &dao_voting::ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![RpcFilterType::Memcmp(discriminator)]),
+                    ..Default::default()
+                },
+            )?;
+            for (address, account) in user_vaults {
+                let (Some(user), Some(vault)) = (
+                    account
+                        .data
+                        .get(8..40)
+                        .and_then(|key| Pubkey::try_from(key).ok()),
+                    account
+                        .data
+                        .get(40..72)
+                        .and_then(|key| Pubkey::try_from(key).ok()),
+                ) else {
+                    continue;
+                };
+                let Some(&vault_id) = vault_ids.get(&vault) else {
+                    continue;
+                };
+                if outdated(
+                    &rpc,
+                    &address,
+                    USER_VAULT_VERSION,
+                    |user_vault: &UserVault| user_vault.version,
+                )? {
+                    let ix =
+                        MigrateUserVaultBuilder::new(signer.pubkey(), vault_id, user).instruction();
+                    println!(
+                        "Migrated user vault of {user} in vault {vault_id}: {}",
+                        send(&rpc, &signer, ix)?
+                    );
+                }
+            }
+        }
+        Command::ListVaults => {
+            let global_state: GlobalState = fetch(&rpc, &pda::find_global_state_address().0)?;
+            for vault_id in 0..global_state.vault_count {
+                let address = pda::find_vault_address(vault_id).0;
+                let vault: Vault = fetch(&rpc, &address)?;
+                // Burned or locked, depending on the mode, against the same cap
+                println!(
+                    "{vault_id}\t{address}\t{:?}\tvotes {}/{}\tdeadline {}",
+                    vault.mode,
+                    quote::vault_vote_total(&vault),
+                    vault.max_vote_cap,
+                    vault.deadline
+                );
+            }
+        }
+        Command::ShowVault { vault_id } => {
+            let address = pda::find_vault_address(vault_id).0;
+            let vault: Vault = fetch(&rpc, &address)?;
+            println!("address:                {address}");
+            println!("owner:                  {}", vault.owner);
+            println!("mode:                   {:?}", vault.mode);
+            println!("vote token mint:        {}", vault.vote_token_mint);
+            println!("project token mint:     {}", vault.project_token_mint);
+            println!("voting start:           {}", vault.voting_start);
+            println!("deadline:               {}", vault.deadline);
+            println!("convert time:           {}", vault.convert_time);
+            println!("max vote cap:           {}", vault.max_vote_cap);
+            println!(
+                "remaining capacity:     {}",
+                quote::remaining_vote_capacity(&vault)
+            );
+            println!("total burned:           {}", vault.total_burned);
+            println!("total locked:           {}", vault.total_locked);
+            println!("total lock weight:      {}", vault.total_ve_weight);
+            println!("total deposited:        {}", vault.total_deposited);
+            println!("max per user:           {:?}", vault.max_per_user);
+            println!("min per vote:           {:?}", vault.min_per_vote);
+            println!("allowlisted:            {}", vault.merkle_root.is_some());
+            println!(
+                "deadline extended:      {}/{}",
+                vault.deadline_extended, vault.max_deadline_extension
+            );
+        }
+        Command::ShowUser { vault_id, user } => {
+            let user = match user {
+                Some(user) => user,
+                None => load_signer()?.pubkey(),
+            };
+            let vault_address = pda::find_vault_address(vault_id).0;
+            let address = pda::find_user_vault_address(&vault_address, &user).0;
+            let vault: Vault = fetch(&rpc, &vault_address)?;
+            let user_vault: UserVault = fetch(&rpc, &address)?;
+            let claim = quote::quote_claim(&vault, &user_vault);
+            let share_bps = quote::share_bps(&vault, &user_vault);
+            println!("address:                {address}");
+            println!("user:                   {}", user_vault.user);
+            println!("vault:                  {}", user_vault.vault);
+            println!("burned:                 {}", user_vault.burned_amount);
+            println!("locked:                 {}", user_vault.locked_amount);
+            println!("lock weight:            {}", user_vault.ve_weight);
+            println!(
+                "share:                  {}.{:02}%",
+                share_bps / 100,
+                share_bps % 100
+            );
+            println!(
+                "remaining capacity:     {}",
+                quote::remaining_user_capacity(&vault, &user_vault)
+            );
+            println!(
+                "claimable:              {} from {}",
+                claim.claim_amount, claim.claimable_at
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn fetch<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T> {
+    let data = rpc
+        .get_account_data(address)
+        .with_context(|| format!("failed to fetch account {address}"))?;
+    T::try_deserialize(&mut data.as_slice())
+        .map_err(|err| anyhow!("failed to decode account {address}: {err}"))
+}
+
+/// Whether the account at `address` predates `version` of its layout:
+/// either too short for the current layout or carrying an older version.
+fn outdated<T: AccountDeserialize + Space>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+    version: u8,
+    account_version: impl Fn(&T) -> u8,
+) -> Result<bool> {
+    let data = rpc
+        .get_account_data(address)
+        .with_context(|| format!("failed to fetch account {address}"))?;
+    if data.len() < 8 + T::INIT_SPACE {
+        return Ok(true);
+    }
+    let value = T::try_deserialize(&mut data.as_slice())
+        .map_err(|err| anyhow!("failed to decode account {address}: {err}"))?;
+    Ok(account_version(&value) < version)
+}
+
+fn send(rpc: &RpcClient, signer: &Keypair, ix: Instruction) -> Result<Signature> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx =
+        Transaction::new_signed_with_payer(&[ix], Some(&signer.pubkey()), &[signer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&tx)?)
+}
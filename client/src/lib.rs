@@ -0,0 +1,1760 @@
+//! Instruction builders for the dao-voting program.
+//!
+//! Each builder takes the signer and instruction arguments and derives every
+//! PDA and associated token account the instruction needs.
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::metadata::mpl_token_metadata;
+
+pub use anchor_lang::prelude::Pubkey;
+pub use dao_voting;
+use dao_voting::{
+    pda, GovernanceAction, ProposalConfig, RoleKind, ScheduleUpdate, TokenMetadata, VaultMode,
+    VoteChoice,
+};
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: dao_voting::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Metaplex metadata account for `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    mpl_token_metadata::accounts::Metadata::find_pda(mint).0
+}
+
+pub struct InitializeBuilder {
+    admin: Pubkey,
+    metadata: TokenMetadata,
+    st_metadata: TokenMetadata,
+    timelock_delay: i64,
+    proposal_config: ProposalConfig,
+}
+
+impl InitializeBuilder {
+    pub fn new(
+        admin: Pubkey,
+        metadata: TokenMetadata,
+        st_metadata: TokenMetadata,
+        timelock_delay: i64,
+        proposal_config: ProposalConfig,
+    ) -> Self {
+        Self {
+            admin,
+            metadata,
+            st_metadata,
+            timelock_delay,
+            proposal_config,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let governance_token_mint = pda::find_governance_token_mint_address().0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::Initialize {
+                global_state: pda::find_global_state_address().0,
+                governance_token_mint,
+                st_governance_token_mint,
+                admin: self.admin,
+                system_program: system_program::ID,
+                token_program: anchor_spl::token::ID,
+                rent: sysvar::rent::ID,
+                metadata: metadata_address(&governance_token_mint),
+                token_metadata_program: mpl_token_metadata::ID,
+                st_metadata: metadata_address(&st_governance_token_mint),
+            },
+            dao_voting::instruction::Initialize {
+                metadata: self.metadata,
+                st_metadata: self.st_metadata,
+                timelock_delay: self.timelock_delay,
+                proposal_config: self.proposal_config,
+            },
+        )
+    }
+}
+
+pub struct ConvertToStGovernanceBuilder {
+    user: Pubkey,
+    amount: u64,
+    treasury: Option<Pubkey>,
+}
+
+impl ConvertToStGovernanceBuilder {
+    pub fn new(user: Pubkey, amount: u64) -> Self {
+        Self {
+            user,
+            amount,
+            treasury: None,
+        }
+    }
+
+    /// Owner of the fee account. Required whenever the conversion fee is
+    /// non-zero.
+    pub fn treasury(mut self, treasury: Pubkey) -> Self {
+        self.treasury = Some(treasury);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let governance_token_mint = pda::find_governance_token_mint_address().0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::ConvertToStGovernance {
+                governance_token_mint,
+                st_governance_token_mint,
+                user_governance_token_account: get_associated_token_address(
+                    &self.user,
+                    &governance_token_mint,
+                ),
+                user_st_governance_token_account: get_associated_token_address(
+                    &self.user,
+                    &st_governance_token_mint,
+                ),
+                user: self.user,
+                global_state: pda::find_global_state_address().0,
+                global_conversion_window: pda::find_global_conversion_window_address().0,
+                user_conversion_window: pda::find_user_conversion_window_address(&self.user).0,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                rent: sysvar::rent::ID,
+                treasury_governance_token_account: self.treasury.map(|treasury| {
+                    get_associated_token_address(&treasury, &governance_token_mint)
+                }),
+            },
+            dao_voting::instruction::ConvertToStGovernance {
+                amount: self.amount,
+            },
+        )
+    }
+}
+
+pub struct CreateStAAndVaultBuilder {
+    creator: Pubkey,
+    vault_id: u64,
+    metadata: TokenMetadata,
+    max_vote_cap: u128,
+    voting_start: i64,
+    deadline: i64,
+    max_per_user: Option<u128>,
+    min_per_vote: Option<u64>,
+    merkle_root: Option<[u8; 32]>,
+    max_deadline_extension: i64,
+    mode: VaultMode,
+    role: Option<Pubkey>,
+}
+
+impl CreateStAAndVaultBuilder {
+    /// `vault_id` must be the current `GlobalState::vault_count`.
+    pub fn new(
+        creator: Pubkey,
+        vault_id: u64,
+        metadata: TokenMetadata,
+        max_vote_cap: u128,
+        voting_start: i64,
+        deadline: i64,
+    ) -> Self {
+        Self {
+            creator,
+            vault_id,
+            metadata,
+            max_vote_cap,
+            voting_start,
+            deadline,
+            max_per_user: None,
+            min_per_vote: None,
+            merkle_root: None,
+            max_deadline_extension: 0,
+            mode: VaultMode::Burn,
+            role: None,
+        }
+    }
+
+    pub fn max_per_user(mut self, max_per_user: u128) -> Self {
+        self.max_per_user = Some(max_per_user);
+        self
+    }
+
+    pub fn min_per_vote(mut self, min_per_vote: u64) -> Self {
+        self.min_per_vote = Some(min_per_vote);
+        self
+    }
+
+    pub fn merkle_root(mut self, merkle_root: [u8; 32]) -> Self {
+        self.merkle_root = Some(merkle_root);
+        self
+    }
+
+    pub fn max_deadline_extension(mut self, max_deadline_extension: i64) -> Self {
+        self.max_deadline_extension = max_deadline_extension;
+        self
+    }
+
+    pub fn mode(mut self, mode: VaultMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Passes the creator's Operator role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Operator, &self.creator).0);
+        self
+    }
+
+    /// Passes `role` as the creator's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        let vote_token_mint = pda::find_vote_token_mint_address(&vault).0;
+        instruction(
+            dao_voting::accounts::CreateStAAndVault {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                vault,
+                vote_token_mint,
+                system_program: system_program::ID,
+                token_program: anchor_spl::token::ID,
+                rent: sysvar::rent::ID,
+                creator: self.creator,
+                metadata: metadata_address(&vote_token_mint),
+                token_metadata_program: mpl_token_metadata::ID,
+            },
+            dao_voting::instruction::CreateStAAndVault {
+                max_vote_cap: self.max_vote_cap,
+                voting_start: self.voting_start,
+                deadline: self.deadline,
+                metadata: self.metadata,
+                max_per_user: self.max_per_user,
+                min_per_vote: self.min_per_vote,
+                merkle_root: self.merkle_root,
+                max_deadline_extension: self.max_deadline_extension,
+                mode: self.mode,
+            },
+        )
+    }
+}
+
+pub struct VoteBuilder {
+    user: Pubkey,
+    vault_id: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+    allocation: Option<u64>,
+}
+
+impl VoteBuilder {
+    pub fn new(user: Pubkey, vault_id: u64, amount: u64) -> Self {
+        Self {
+            user,
+            vault_id,
+            amount,
+            proof: Vec::new(),
+            allocation: None,
+        }
+    }
+
+    /// Merkle proof for vaults with an allowlist.
+    pub fn proof(mut self, proof: Vec<[u8; 32]>) -> Self {
+        self.proof = proof;
+        self
+    }
+
+    /// Allocation committed to in the user's allowlist leaf, if any.
+    pub fn allocation(mut self, allocation: u64) -> Self {
+        self.allocation = Some(allocation);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        let vote_token_mint = pda::find_vote_token_mint_address(&vault).0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::Vote {
+                global_state: pda::find_global_state_address().0,
+                vote_token_mint,
+                user_vote_token_account: get_associated_token_address(&self.user, &vote_token_mint),
+                vault,
+                user_vault: pda::find_user_vault_address(&vault, &self.user).0,
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                st_governance_token_mint,
+                user_st_governance_token_account: get_associated_token_address(
+                    &self.user,
+                    &st_governance_token_mint,
+                ),
+            },
+            dao_voting::instruction::Vote {
+                vault_id: self.vault_id,
+                amount: self.amount,
+                proof: self.proof,
+                allocation: self.allocation,
+            },
+        )
+    }
+}
+
+pub struct SetProjectTokenBuilder {
+    owner: Pubkey,
+    vault_id: u64,
+    project_token_mint: Pubkey,
+    convert_time: i64,
+    role: Option<Pubkey>,
+}
+
+impl SetProjectTokenBuilder {
+    pub fn new(
+        owner: Pubkey,
+        vault_id: u64,
+        project_token_mint: Pubkey,
+        convert_time: i64,
+    ) -> Self {
+        Self {
+            owner,
+            vault_id,
+            project_token_mint,
+            convert_time,
+            role: None,
+        }
+    }
+
+    /// Passes the owner's Operator role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Operator, &self.owner).0);
+        self
+    }
+
+    /// Passes `role` as the owner's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::SetProjectToken {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                vault: pda::find_vault_address(self.vault_id).0,
+                owner: self.owner,
+            },
+            dao_voting::instruction::SetProjectToken {
+                project_token_mint: self.project_token_mint,
+                convert_time: self.convert_time,
+            },
+        )
+    }
+}
+
+pub struct DepositProjectTokensBuilder {
+    project_authority: Pubkey,
+    vault_id: u64,
+    project_token_mint: Pubkey,
+    amount: u64,
+    role: Option<Pubkey>,
+}
+
+impl DepositProjectTokensBuilder {
+    pub fn new(
+        project_authority: Pubkey,
+        vault_id: u64,
+        project_token_mint: Pubkey,
+        amount: u64,
+    ) -> Self {
+        Self {
+            project_authority,
+            vault_id,
+            project_token_mint,
+            amount,
+            role: None,
+        }
+    }
+
+    /// Passes the depositor's ProjectPartner role. Not needed for the global
+    /// admin.
+    pub fn with_role(mut self) -> Self {
+        self.role =
+            Some(pda::find_role_address(RoleKind::ProjectPartner, &self.project_authority).0);
+        self
+    }
+
+    /// Passes `role` as the depositor's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        instruction(
+            dao_voting::accounts::DepositProjectTokens {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                vault,
+                project_token_account: get_associated_token_address(
+                    &self.project_authority,
+                    &self.project_token_mint,
+                ),
+                vault_token_account: get_associated_token_address(&vault, &self.project_token_mint),
+                project_token_mint: self.project_token_mint,
+                project_authority: self.project_authority,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            dao_voting::instruction::DepositProjectTokens {
+                amount: self.amount,
+            },
+        )
+    }
+}
+
+pub struct ClaimProjectTokensBuilder {
+    user: Pubkey,
+    vault_id: u64,
+    project_token_mint: Pubkey,
+}
+
+impl ClaimProjectTokensBuilder {
+    /// `project_token_mint` is the vault's `project_token_mint`.
+    pub fn new(user: Pubkey, vault_id: u64, project_token_mint: Pubkey) -> Self {
+        Self {
+            user,
+            vault_id,
+            project_token_mint,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        let vote_token_mint = pda::find_vote_token_mint_address(&vault).0;
+        instruction(
+            dao_voting::accounts::ClaimProjectTokens {
+                global_state: pda::find_global_state_address().0,
+                vault,
+                vote_token_mint,
+                project_token_mint: self.project_token_mint,
+                user_vote_token_account: get_associated_token_address(&self.user, &vote_token_mint),
+                vault_token_account: get_associated_token_address(&vault, &self.project_token_mint),
+                user_project_token_account: get_associated_token_address(
+                    &self.user,
+                    &self.project_token_mint,
+                ),
+                user_vault: pda::find_user_vault_address(&vault, &self.user).0,
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            dao_voting::instruction::ClaimProjectTokens {
+                vault_id: self.vault_id,
+            },
+        )
+    }
+}
+
+pub struct PauseBuilder {
+    pauser: Pubkey,
+    role: Option<Pubkey>,
+}
+
+impl PauseBuilder {
+    pub fn new(pauser: Pubkey) -> Self {
+        Self { pauser, role: None }
+    }
+
+    /// Passes the signer's Pauser role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Pauser, &self.pauser).0);
+        self
+    }
+
+    /// Passes `role` as the signer's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::Pause {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                pauser: self.pauser,
+            },
+            dao_voting::instruction::Pause {},
+        )
+    }
+}
+
+pub struct GrantRoleBuilder {
+    payer: Pubkey,
+    proposer: Pubkey,
+    id: u64,
+    kind: RoleKind,
+    holder: Pubkey,
+}
+
+impl GrantRoleBuilder {
+    /// Executes the queued `GrantRole` action `id`. `proposer` queued it and
+    /// gets its rent back, `payer` funds the role account.
+    pub fn new(payer: Pubkey, proposer: Pubkey, id: u64, kind: RoleKind, holder: Pubkey) -> Self {
+        Self {
+            payer,
+            proposer,
+            id,
+            kind,
+            holder,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::GrantRole {
+                queued_action: pda::find_queued_action_address(self.id).0,
+                proposer: self.proposer,
+                role: pda::find_role_address(self.kind, &self.holder).0,
+                holder: self.holder,
+                payer: self.payer,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::GrantRole {
+                id: self.id,
+                kind: self.kind,
+            },
+        )
+    }
+}
+
+pub struct RevokeRoleBuilder {
+    proposer: Pubkey,
+    id: u64,
+    role: Pubkey,
+    granted_by: Pubkey,
+}
+
+impl RevokeRoleBuilder {
+    /// Executes the queued `RevokeRole` action `id`. `proposer` queued it and
+    /// gets its rent back. `role` is the role account being revoked and
+    /// `granted_by` its `Role::granted_by`, which gets the role's rent back.
+    pub fn new(proposer: Pubkey, id: u64, role: Pubkey, granted_by: Pubkey) -> Self {
+        Self {
+            proposer,
+            id,
+            role,
+            granted_by,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::RevokeRole {
+                queued_action: pda::find_queued_action_address(self.id).0,
+                proposer: self.proposer,
+                role: self.role,
+                granted_by: self.granted_by,
+            },
+            dao_voting::instruction::RevokeRole { id: self.id },
+        )
+    }
+}
+
+pub struct QueueActionBuilder {
+    proposer: Pubkey,
+    id: u64,
+    action: GovernanceAction,
+    role: Option<Pubkey>,
+}
+
+impl QueueActionBuilder {
+    /// `id` must be the current `GlobalState::queued_action_count`.
+    pub fn new(proposer: Pubkey, id: u64, action: GovernanceAction) -> Self {
+        Self {
+            proposer,
+            id,
+            action,
+            role: None,
+        }
+    }
+
+    /// Passes the proposer's Admin role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Admin, &self.proposer).0);
+        self
+    }
+
+    /// Passes `role` as the proposer's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::QueueAction {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                queued_action: pda::find_queued_action_address(self.id).0,
+                proposer: self.proposer,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::QueueAction {
+                action: self.action,
+            },
+        )
+    }
+}
+
+pub struct ExecuteActionBuilder {
+    proposer: Pubkey,
+    id: u64,
+}
+
+impl ExecuteActionBuilder {
+    /// `proposer` queued the action and gets its rent back.
+    pub fn new(proposer: Pubkey, id: u64) -> Self {
+        Self { proposer, id }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::ExecuteAction {
+                global_state: pda::find_global_state_address().0,
+                queued_action: pda::find_queued_action_address(self.id).0,
+                proposer: self.proposer,
+            },
+            dao_voting::instruction::ExecuteAction { id: self.id },
+        )
+    }
+}
+
+pub struct CancelActionBuilder {
+    authority: Pubkey,
+    proposer: Pubkey,
+    id: u64,
+    role: Option<Pubkey>,
+}
+
+impl CancelActionBuilder {
+    /// `proposer` queued the action and gets its rent back.
+    pub fn new(authority: Pubkey, proposer: Pubkey, id: u64) -> Self {
+        Self {
+            authority,
+            proposer,
+            id,
+            role: None,
+        }
+    }
+
+    /// Passes the authority's Admin role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Admin, &self.authority).0);
+        self
+    }
+
+    /// Passes `role` as the authority's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::CancelAction {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                queued_action: pda::find_queued_action_address(self.id).0,
+                proposer: self.proposer,
+                authority: self.authority,
+            },
+            dao_voting::instruction::CancelAction { id: self.id },
+        )
+    }
+}
+
+pub struct CreateProposalBuilder {
+    proposer: Pubkey,
+    id: u64,
+    action: GovernanceAction,
+}
+
+impl CreateProposalBuilder {
+    /// `id` must be the current `GlobalState::proposal_count`.
+    pub fn new(proposer: Pubkey, id: u64, action: GovernanceAction) -> Self {
+        Self {
+            proposer,
+            id,
+            action,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::CreateProposal {
+                global_state: pda::find_global_state_address().0,
+                proposal: pda::find_proposal_address(self.id).0,
+                proposer: self.proposer,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::CreateProposal {
+                action: self.action,
+            },
+        )
+    }
+}
+
+pub struct CastProposalVoteBuilder {
+    voter: Pubkey,
+    id: u64,
+    choice: VoteChoice,
+    amount: u64,
+}
+
+impl CastProposalVoteBuilder {
+    pub fn new(voter: Pubkey, id: u64, choice: VoteChoice, amount: u64) -> Self {
+        Self {
+            voter,
+            id,
+            choice,
+            amount,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let proposal = pda::find_proposal_address(self.id).0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::CastProposalVote {
+                global_state: pda::find_global_state_address().0,
+                proposal,
+                proposal_vote: pda::find_proposal_vote_address(&proposal, &self.voter).0,
+                st_governance_token_mint,
+                voter_st_governance_token_account: get_associated_token_address(
+                    &self.voter,
+                    &st_governance_token_mint,
+                ),
+                proposal_token_account: get_associated_token_address(
+                    &proposal,
+                    &st_governance_token_mint,
+                ),
+                voter: self.voter,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            dao_voting::instruction::CastProposalVote {
+                id: self.id,
+                choice: self.choice,
+                amount: self.amount,
+            },
+        )
+    }
+}
+
+pub struct WithdrawProposalVoteBuilder {
+    voter: Pubkey,
+    id: u64,
+}
+
+impl WithdrawProposalVoteBuilder {
+    pub fn new(voter: Pubkey, id: u64) -> Self {
+        Self { voter, id }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let proposal = pda::find_proposal_address(self.id).0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::WithdrawProposalVote {
+                global_state: pda::find_global_state_address().0,
+                proposal,
+                proposal_vote: pda::find_proposal_vote_address(&proposal, &self.voter).0,
+                st_governance_token_mint,
+                voter_st_governance_token_account: get_associated_token_address(
+                    &self.voter,
+                    &st_governance_token_mint,
+                ),
+                proposal_token_account: get_associated_token_address(
+                    &proposal,
+                    &st_governance_token_mint,
+                ),
+                voter: self.voter,
+                token_program: anchor_spl::token::ID,
+            },
+            dao_voting::instruction::WithdrawProposalVote { id: self.id },
+        )
+    }
+}
+
+pub struct ExecuteProposalBuilder {
+    payer: Pubkey,
+    id: u64,
+    queued_action_id: u64,
+}
+
+impl ExecuteProposalBuilder {
+    /// Queues the passed proposal's action as `queued_action_id`, which must
+    /// be the current `GlobalState::queued_action_count`.
+    pub fn new(payer: Pubkey, id: u64, queued_action_id: u64) -> Self {
+        Self {
+            payer,
+            id,
+            queued_action_id,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::ExecuteProposal {
+                global_state: pda::find_global_state_address().0,
+                proposal: pda::find_proposal_address(self.id).0,
+                queued_action: pda::find_queued_action_address(self.queued_action_id).0,
+                payer: self.payer,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::ExecuteProposal { id: self.id },
+        )
+    }
+}
+
+pub struct LockVoteBuilder {
+    user: Pubkey,
+    vault_id: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+    allocation: Option<u64>,
+}
+
+impl LockVoteBuilder {
+    pub fn new(user: Pubkey, vault_id: u64, amount: u64) -> Self {
+        Self {
+            user,
+            vault_id,
+            amount,
+            proof: Vec::new(),
+            allocation: None,
+        }
+    }
+
+    /// Merkle proof for vaults with an allowlist.
+    pub fn proof(mut self, proof: Vec<[u8; 32]>) -> Self {
+        self.proof = proof;
+        self
+    }
+
+    /// Allocation committed to in the user's allowlist leaf, if any.
+    pub fn allocation(mut self, allocation: u64) -> Self {
+        self.allocation = Some(allocation);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::LockVote {
+                global_state: pda::find_global_state_address().0,
+                vault,
+                user_vault: pda::find_user_vault_address(&vault, &self.user).0,
+                st_governance_token_mint,
+                user_st_governance_token_account: get_associated_token_address(
+                    &self.user,
+                    &st_governance_token_mint,
+                ),
+                vault_st_governance_token_account: get_associated_token_address(
+                    &vault,
+                    &st_governance_token_mint,
+                ),
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            dao_voting::instruction::LockVote {
+                _vault_id: self.vault_id,
+                amount: self.amount,
+                proof: self.proof,
+                allocation: self.allocation,
+            },
+        )
+    }
+}
+
+pub struct UnlockVoteBuilder {
+    user: Pubkey,
+    vault_id: u64,
+}
+
+impl UnlockVoteBuilder {
+    pub fn new(user: Pubkey, vault_id: u64) -> Self {
+        Self { user, vault_id }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::UnlockVote {
+                vault,
+                user_vault: pda::find_user_vault_address(&vault, &self.user).0,
+                st_governance_token_mint,
+                user_st_governance_token_account: get_associated_token_address(
+                    &self.user,
+                    &st_governance_token_mint,
+                ),
+                vault_st_governance_token_account: get_associated_token_address(
+                    &vault,
+                    &st_governance_token_mint,
+                ),
+                user: self.user,
+                token_program: anchor_spl::token::ID,
+            },
+            dao_voting::instruction::UnlockVote {
+                vault_id: self.vault_id,
+            },
+        )
+    }
+}
+
+pub struct StakeStGovernanceBuilder {
+    owner: Pubkey,
+    amount: u64,
+}
+
+impl StakeStGovernanceBuilder {
+    pub fn new(owner: Pubkey, amount: u64) -> Self {
+        Self { owner, amount }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let global_state = pda::find_global_state_address().0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::StakeStGovernance {
+                global_state,
+                stake_position: pda::find_stake_position_address(&self.owner).0,
+                st_governance_token_mint,
+                owner_st_governance_token_account: get_associated_token_address(
+                    &self.owner,
+                    &st_governance_token_mint,
+                ),
+                stake_token_account: get_associated_token_address(
+                    &global_state,
+                    &st_governance_token_mint,
+                ),
+                owner: self.owner,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            dao_voting::instruction::StakeStGovernance {
+                amount: self.amount,
+            },
+        )
+    }
+}
+
+pub struct UnstakeStGovernanceBuilder {
+    owner: Pubkey,
+    amount: u64,
+}
+
+impl UnstakeStGovernanceBuilder {
+    pub fn new(owner: Pubkey, amount: u64) -> Self {
+        Self { owner, amount }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let global_state = pda::find_global_state_address().0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::UnstakeStGovernance {
+                global_state,
+                stake_position: pda::find_stake_position_address(&self.owner).0,
+                st_governance_token_mint,
+                owner_st_governance_token_account: get_associated_token_address(
+                    &self.owner,
+                    &st_governance_token_mint,
+                ),
+                stake_token_account: get_associated_token_address(
+                    &global_state,
+                    &st_governance_token_mint,
+                ),
+                owner: self.owner,
+                token_program: anchor_spl::token::ID,
+            },
+            dao_voting::instruction::UnstakeStGovernance {
+                amount: self.amount,
+            },
+        )
+    }
+}
+
+pub struct ClaimStakingRewardsBuilder {
+    owner: Pubkey,
+}
+
+impl ClaimStakingRewardsBuilder {
+    pub fn new(owner: Pubkey) -> Self {
+        Self { owner }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let global_state = pda::find_global_state_address().0;
+        let governance_token_mint = pda::find_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::ClaimStakingRewards {
+                global_state,
+                stake_position: pda::find_stake_position_address(&self.owner).0,
+                governance_token_mint,
+                owner_governance_token_account: get_associated_token_address(
+                    &self.owner,
+                    &governance_token_mint,
+                ),
+                reward_token_account: get_associated_token_address(
+                    &global_state,
+                    &governance_token_mint,
+                ),
+                owner: self.owner,
+                token_program: anchor_spl::token::ID,
+            },
+            dao_voting::instruction::ClaimStakingRewards {},
+        )
+    }
+}
+
+pub struct FundRewardsBuilder {
+    treasurer: Pubkey,
+    amount: u64,
+    duration: i64,
+    role: Option<Pubkey>,
+}
+
+impl FundRewardsBuilder {
+    pub fn new(treasurer: Pubkey, amount: u64, duration: i64) -> Self {
+        Self {
+            treasurer,
+            amount,
+            duration,
+            role: None,
+        }
+    }
+
+    /// Passes the treasurer's Treasurer role. Not needed for the global
+    /// admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Treasurer, &self.treasurer).0);
+        self
+    }
+
+    /// Passes `role` as the treasurer's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let global_state = pda::find_global_state_address().0;
+        let governance_token_mint = pda::find_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::FundRewards {
+                global_state,
+                role: self.role,
+                governance_token_mint,
+                treasurer_governance_token_account: get_associated_token_address(
+                    &self.treasurer,
+                    &governance_token_mint,
+                ),
+                reward_token_account: get_associated_token_address(
+                    &global_state,
+                    &governance_token_mint,
+                ),
+                treasurer: self.treasurer,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            dao_voting::instruction::FundRewards {
+                amount: self.amount,
+                duration: self.duration,
+            },
+        )
+    }
+}
+
+pub struct CreateVeLockBuilder {
+    owner: Pubkey,
+    amount: u64,
+    duration: i64,
+}
+
+impl CreateVeLockBuilder {
+    pub fn new(owner: Pubkey, amount: u64, duration: i64) -> Self {
+        Self {
+            owner,
+            amount,
+            duration,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let ve_lock = pda::find_ve_lock_address(&self.owner).0;
+        let governance_token_mint = pda::find_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::CreateVeLock {
+                global_state: pda::find_global_state_address().0,
+                ve_lock,
+                governance_token_mint,
+                owner_governance_token_account: get_associated_token_address(
+                    &self.owner,
+                    &governance_token_mint,
+                ),
+                ve_lock_token_account: get_associated_token_address(
+                    &ve_lock,
+                    &governance_token_mint,
+                ),
+                owner: self.owner,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            dao_voting::instruction::CreateVeLock {
+                amount: self.amount,
+                duration: self.duration,
+            },
+        )
+    }
+}
+
+pub struct WithdrawVeLockBuilder {
+    owner: Pubkey,
+}
+
+impl WithdrawVeLockBuilder {
+    pub fn new(owner: Pubkey) -> Self {
+        Self { owner }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let ve_lock = pda::find_ve_lock_address(&self.owner).0;
+        let governance_token_mint = pda::find_governance_token_mint_address().0;
+        instruction(
+            dao_voting::accounts::WithdrawVeLock {
+                global_state: pda::find_global_state_address().0,
+                ve_lock,
+                governance_token_mint,
+                owner_governance_token_account: get_associated_token_address(
+                    &self.owner,
+                    &governance_token_mint,
+                ),
+                ve_lock_token_account: get_associated_token_address(
+                    &ve_lock,
+                    &governance_token_mint,
+                ),
+                owner: self.owner,
+                token_program: anchor_spl::token::ID,
+            },
+            dao_voting::instruction::WithdrawVeLock {},
+        )
+    }
+}
+
+pub struct VeVoteBuilder {
+    user: Pubkey,
+    vault_id: u64,
+    proof: Vec<[u8; 32]>,
+    allocation: Option<u64>,
+}
+
+impl VeVoteBuilder {
+    pub fn new(user: Pubkey, vault_id: u64) -> Self {
+        Self {
+            user,
+            vault_id,
+            proof: Vec::new(),
+            allocation: None,
+        }
+    }
+
+    /// Merkle proof for vaults with an allowlist.
+    pub fn proof(mut self, proof: Vec<[u8; 32]>) -> Self {
+        self.proof = proof;
+        self
+    }
+
+    /// Allocation committed to in the user's allowlist leaf, if any.
+    pub fn allocation(mut self, allocation: u64) -> Self {
+        self.allocation = Some(allocation);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        instruction(
+            dao_voting::accounts::VeVote {
+                global_state: pda::find_global_state_address().0,
+                vault,
+                user_vault: pda::find_user_vault_address(&vault, &self.user).0,
+                ve_lock: pda::find_ve_lock_address(&self.user).0,
+                user: self.user,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::VeVote {
+                _vault_id: self.vault_id,
+                proof: self.proof,
+                allocation: self.allocation,
+            },
+        )
+    }
+}
+
+pub struct SetMerkleRootBuilder {
+    owner: Pubkey,
+    vault_id: u64,
+    merkle_root: Option<[u8; 32]>,
+    role: Option<Pubkey>,
+}
+
+impl SetMerkleRootBuilder {
+    pub fn new(owner: Pubkey, vault_id: u64, merkle_root: Option<[u8; 32]>) -> Self {
+        Self {
+            owner,
+            vault_id,
+            merkle_root,
+            role: None,
+        }
+    }
+
+    /// Passes the owner's Operator role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Operator, &self.owner).0);
+        self
+    }
+
+    /// Passes `role` as the owner's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::SetMerkleRoot {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                vault: pda::find_vault_address(self.vault_id).0,
+                owner: self.owner,
+            },
+            dao_voting::instruction::SetMerkleRoot {
+                merkle_root: self.merkle_root,
+            },
+        )
+    }
+}
+
+pub struct UpdateVotingWindowBuilder {
+    owner: Pubkey,
+    vault_id: u64,
+    voting_start: i64,
+    deadline: i64,
+    role: Option<Pubkey>,
+}
+
+impl UpdateVotingWindowBuilder {
+    pub fn new(owner: Pubkey, vault_id: u64, voting_start: i64, deadline: i64) -> Self {
+        Self {
+            owner,
+            vault_id,
+            voting_start,
+            deadline,
+            role: None,
+        }
+    }
+
+    /// Passes the owner's Operator role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Operator, &self.owner).0);
+        self
+    }
+
+    /// Passes `role` as the owner's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::UpdateVotingWindow {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                vault: pda::find_vault_address(self.vault_id).0,
+                owner: self.owner,
+            },
+            dao_voting::instruction::UpdateVotingWindow {
+                voting_start: self.voting_start,
+                deadline: self.deadline,
+            },
+        )
+    }
+}
+
+pub struct UpdateVaultScheduleBuilder {
+    owner: Pubkey,
+    vault_id: u64,
+    update: ScheduleUpdate,
+    role: Option<Pubkey>,
+}
+
+impl UpdateVaultScheduleBuilder {
+    pub fn new(owner: Pubkey, vault_id: u64, update: ScheduleUpdate) -> Self {
+        Self {
+            owner,
+            vault_id,
+            update,
+            role: None,
+        }
+    }
+
+    /// Passes the owner's Operator role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Operator, &self.owner).0);
+        self
+    }
+
+    /// Passes `role` as the owner's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::UpdateVaultSchedule {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                vault: pda::find_vault_address(self.vault_id).0,
+                owner: self.owner,
+            },
+            dao_voting::instruction::UpdateVaultSchedule {
+                update: self.update,
+            },
+        )
+    }
+}
+
+pub struct UpdateMaxVoteCapBuilder {
+    owner: Pubkey,
+    vault_id: u64,
+    new_max_vote_cap: u128,
+    role: Option<Pubkey>,
+}
+
+impl UpdateMaxVoteCapBuilder {
+    pub fn new(owner: Pubkey, vault_id: u64, new_max_vote_cap: u128) -> Self {
+        Self {
+            owner,
+            vault_id,
+            new_max_vote_cap,
+            role: None,
+        }
+    }
+
+    /// Passes the owner's Operator role. Not needed for the global admin.
+    pub fn with_role(mut self) -> Self {
+        self.role = Some(pda::find_role_address(RoleKind::Operator, &self.owner).0);
+        self
+    }
+
+    /// Passes `role` as the owner's role account, such as a role of another
+    /// kind.
+    pub fn role(mut self, role: Pubkey) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::UpdateMaxVoteCap {
+                global_state: pda::find_global_state_address().0,
+                role: self.role,
+                vault: pda::find_vault_address(self.vault_id).0,
+                owner: self.owner,
+            },
+            dao_voting::instruction::UpdateMaxVoteCap {
+                new_max_vote_cap: self.new_max_vote_cap,
+            },
+        )
+    }
+}
+
+pub struct QuoteClaimBuilder {
+    user: Pubkey,
+    vault_id: u64,
+}
+
+impl QuoteClaimBuilder {
+    pub fn new(user: Pubkey, vault_id: u64) -> Self {
+        Self { user, vault_id }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        instruction(
+            dao_voting::accounts::QuoteClaim {
+                vault,
+                user_vault: pda::find_user_vault_address(&vault, &self.user).0,
+                user: self.user,
+            },
+            dao_voting::instruction::QuoteClaim {
+                _vault_id: self.vault_id,
+            },
+        )
+    }
+}
+
+pub struct QuoteVoteBuilder {
+    user: Pubkey,
+    vault_id: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+    allocation: Option<u64>,
+    user_vault: bool,
+}
+
+impl QuoteVoteBuilder {
+    pub fn new(user: Pubkey, vault_id: u64, amount: u64) -> Self {
+        Self {
+            user,
+            vault_id,
+            amount,
+            proof: Vec::new(),
+            allocation: None,
+            user_vault: false,
+        }
+    }
+
+    /// Counts the user's existing votes. Leave it out until the user has
+    /// voted, since the account doesn't exist before then.
+    pub fn with_user_vault(mut self) -> Self {
+        self.user_vault = true;
+        self
+    }
+
+    /// Merkle proof for vaults with an allowlist.
+    pub fn proof(mut self, proof: Vec<[u8; 32]>) -> Self {
+        self.proof = proof;
+        self
+    }
+
+    /// Allocation committed to in the user's allowlist leaf, if any.
+    pub fn allocation(mut self, allocation: u64) -> Self {
+        self.allocation = Some(allocation);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        instruction(
+            dao_voting::accounts::QuoteVote {
+                global_state: pda::find_global_state_address().0,
+                vault,
+                user_vault: self
+                    .user_vault
+                    .then(|| pda::find_user_vault_address(&vault, &self.user).0),
+                user: self.user,
+            },
+            dao_voting::instruction::QuoteVote {
+                _vault_id: self.vault_id,
+                amount: self.amount,
+                proof: self.proof,
+                allocation: self.allocation,
+            },
+        )
+    }
+}
+
+pub struct VaultSummaryBuilder {
+    vault_id: u64,
+}
+
+impl VaultSummaryBuilder {
+    pub fn new(vault_id: u64) -> Self {
+        Self { vault_id }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::ViewVault {
+                vault: pda::find_vault_address(self.vault_id).0,
+            },
+            dao_voting::instruction::VaultSummary {
+                _vault_id: self.vault_id,
+            },
+        )
+    }
+}
+
+pub struct MigrateGlobalStateBuilder {
+    payer: Pubkey,
+}
+
+impl MigrateGlobalStateBuilder {
+    pub fn new(payer: Pubkey) -> Self {
+        Self { payer }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::MigrateGlobalState {
+                global_state: pda::find_global_state_address().0,
+                payer: self.payer,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::MigrateGlobalState {},
+        )
+    }
+}
+
+pub struct MigrateVaultBuilder {
+    payer: Pubkey,
+    vault_id: u64,
+}
+
+impl MigrateVaultBuilder {
+    pub fn new(payer: Pubkey, vault_id: u64) -> Self {
+        Self { payer, vault_id }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        instruction(
+            dao_voting::accounts::MigrateVault {
+                vault: pda::find_vault_address(self.vault_id).0,
+                payer: self.payer,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::MigrateVault {
+                _vault_id: self.vault_id,
+            },
+        )
+    }
+}
+
+pub struct MigrateUserVaultBuilder {
+    payer: Pubkey,
+    vault_id: u64,
+    user: Pubkey,
+}
+
+impl MigrateUserVaultBuilder {
+    pub fn new(payer: Pubkey, vault_id: u64, user: Pubkey) -> Self {
+        Self {
+            payer,
+            vault_id,
+            user,
+        }
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let vault = pda::find_vault_address(self.vault_id).0;
+        instruction(
+            dao_voting::accounts::MigrateUserVault {
+                vault,
+                user: self.user,
+                user_vault: pda::find_user_vault_address(&vault, &self.user).0,
+                payer: self.payer,
+                system_program: system_program::ID,
+            },
+            dao_voting::instruction::MigrateUserVault {
+                _vault_id: self.vault_id,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AnchorDeserialize, Discriminator};
+    use dao_voting::instruction as ix;
+
+    use super::*;
+
+    fn args<T: AnchorDeserialize + Discriminator>(instruction: &Instruction) -> T {
+        let (discriminator, data) = instruction.data.split_at(8);
+        assert_eq!(discriminator, T::DISCRIMINATOR);
+        T::try_from_slice(data).unwrap()
+    }
+
+    #[test]
+    fn derives_vote_accounts() {
+        let user = Pubkey::new_unique();
+        let instruction = VoteBuilder::new(user, 3, 150)
+            .proof(vec![[1; 32]])
+            .allocation(200)
+            .instruction();
+        assert_eq!(instruction.program_id, dao_voting::ID);
+
+        let vault = pda::find_vault_address(3).0;
+        let vote_token_mint = pda::find_vote_token_mint_address(&vault).0;
+        let st_governance_token_mint = pda::find_st_governance_token_mint_address().0;
+        let keys: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                pda::find_global_state_address().0,
+                vote_token_mint,
+                get_associated_token_address(&user, &vote_token_mint),
+                vault,
+                pda::find_user_vault_address(&vault, &user).0,
+                user,
+                anchor_spl::token::ID,
+                system_program::ID,
+                anchor_spl::associated_token::ID,
+                st_governance_token_mint,
+                get_associated_token_address(&user, &st_governance_token_mint),
+            ]
+        );
+        let signers: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(signers, vec![user]);
+        assert!(instruction.accounts[3].is_writable);
+        assert!(!instruction.accounts[6].is_writable);
+
+        let vote: ix::Vote = args(&instruction);
+        assert_eq!((vote.vault_id, vote.amount), (3, 150));
+        assert_eq!((vote.proof, vote.allocation), (vec![[1; 32]], Some(200)));
+    }
+
+    #[test]
+    fn passes_role_accounts() {
+        let operator = Pubkey::new_unique();
+        // Optional accounts that are left out become the program id
+        let instruction = UpdateMaxVoteCapBuilder::new(operator, 0, 10).instruction();
+        assert_eq!(instruction.accounts[1].pubkey, dao_voting::ID);
+        assert!(!instruction.accounts[1].is_writable);
+
+        let instruction = UpdateMaxVoteCapBuilder::new(operator, 0, 10)
+            .with_role()
+            .instruction();
+        assert_eq!(
+            instruction.accounts[1].pubkey,
+            pda::find_role_address(RoleKind::Operator, &operator).0
+        );
+
+        let other = Pubkey::new_unique();
+        let instruction = PauseBuilder::new(operator).role(other).instruction();
+        assert_eq!(instruction.accounts[1].pubkey, other);
+
+        let instruction = FundRewardsBuilder::new(operator, 100, 10)
+            .with_role()
+            .instruction();
+        assert_eq!(
+            instruction.accounts[1].pubkey,
+            pda::find_role_address(RoleKind::Treasurer, &operator).0
+        );
+
+        let holder = Pubkey::new_unique();
+        let instruction =
+            GrantRoleBuilder::new(operator, holder, 4, RoleKind::Pauser, holder).instruction();
+        let keys: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(keys[0], pda::find_queued_action_address(4).0);
+        assert_eq!(keys[2], pda::find_role_address(RoleKind::Pauser, &holder).0);
+        let grant: ix::GrantRole = args(&instruction);
+        assert_eq!((grant.id, grant.kind), (4, RoleKind::Pauser));
+
+        // The rent goes back to whoever granted the role
+        let role = pda::find_role_address(RoleKind::Pauser, &holder).0;
+        let instruction = RevokeRoleBuilder::new(operator, 5, role, holder).instruction();
+        assert_eq!(instruction.accounts[2].pubkey, role);
+        assert!(instruction.accounts[3].is_writable);
+        assert_eq!(instruction.accounts[3].pubkey, holder);
+        assert!(instruction.accounts.iter().all(|meta| !meta.is_signer));
+    }
+
+    #[test]
+    fn derives_token_accounts_from_owners() {
+        let user = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let governance_token_mint = pda::find_governance_token_mint_address().0;
+        let instruction = ConvertToStGovernanceBuilder::new(user, 5)
+            .treasury(treasury)
+            .instruction();
+        assert_eq!(
+            instruction.accounts.last().unwrap().pubkey,
+            get_associated_token_address(&treasury, &governance_token_mint)
+        );
+        assert_eq!(
+            instruction.accounts[7].pubkey,
+            pda::find_user_conversion_window_address(&user).0
+        );
+
+        let ve_lock = pda::find_ve_lock_address(&user).0;
+        let instruction = CreateVeLockBuilder::new(user, 5, 60).instruction();
+        assert_eq!(instruction.accounts[1].pubkey, ve_lock);
+        assert_eq!(
+            instruction.accounts[4].pubkey,
+            get_associated_token_address(&ve_lock, &governance_token_mint)
+        );
+
+        let proposal = pda::find_proposal_address(2).0;
+        let instruction = CastProposalVoteBuilder::new(user, 2, VoteChoice::Yes, 5).instruction();
+        assert_eq!(instruction.accounts[1].pubkey, proposal);
+        assert_eq!(
+            instruction.accounts[2].pubkey,
+            pda::find_proposal_vote_address(&proposal, &user).0
+        );
+    }
+
+    #[test]
+    fn includes_the_user_vault_in_vote_quotes_on_request() {
+        let user = Pubkey::new_unique();
+        let vault = pda::find_vault_address(1).0;
+        let instruction = QuoteVoteBuilder::new(user, 1, 5).instruction();
+        assert_eq!(instruction.accounts[2].pubkey, dao_voting::ID);
+
+        let instruction = QuoteVoteBuilder::new(user, 1, 5)
+            .with_user_vault()
+            .instruction();
+        assert_eq!(
+            instruction.accounts[2].pubkey,
+            pda::find_user_vault_address(&vault, &user).0
+        );
+        assert_eq!(args::<ix::QuoteVote>(&instruction).amount, 5);
+    }
+}
@@ -0,0 +1,102 @@
+//! Read-only JSON API over the store.
+//!
+//! | Route                        | Returns                             |
+//! |------------------------------|-------------------------------------|
+//! | `GET /status`                | last indexed signature and slot     |
+//! | `GET /vaults`                | every vault                         |
+//! | `GET /vaults/{id}`           | one vault                           |
+//! | `GET /vaults/{id}/positions` | user positions in the vault         |
+//! | `GET /vaults/{id}/votes`     | votes, locks and unlocks            |
+//! | `GET /vaults/{id}/deposits`  | project token deposits              |
+//! | `GET /vaults/{id}/claims`    | project token claims                |
+//! | `GET /vaults/{id}/updates`   | deadline and max vote cap changes   |
+//! | `GET /users/{key}/positions` | the user's positions in every vault |
+//! | `GET /users/{key}/votes`     | the user's votes in every vault     |
+//! | `GET /users/{key}/claims`    | the user's claims in every vault    |
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::store::{Filter, Store};
+
+pub fn serve(listen: &str, database: &Path) -> Result<()> {
+    let server =
+        Server::http(listen).map_err(|err| anyhow!("failed to listen on {listen}: {err}"))?;
+    let store = Store::open(database)?;
+    for request in server.incoming_requests() {
+        let (status, body) = match route(&store, &request) {
+            Ok(Some(body)) => (200, body),
+            Ok(None) => (404, error("not found")),
+            Err(err) => (500, error(&err.to_string())),
+        };
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header("Content-Type", "application/json"))
+            .with_header(header("Access-Control-Allow-Origin", "*"));
+        if let Err(err) = request.respond(response) {
+            eprintln!("failed to respond: {err}");
+        }
+    }
+    Ok(())
+}
+
+fn route(store: &Store, request: &Request) -> Result<Option<String>> {
+    if *request.method() != Method::Get {
+        return Ok(None);
+    }
+    let path = request.url().split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let body = match segments.as_slice() {
+        ["status"] => json(&store.cursor()?),
+        ["vaults"] => json(&store.vaults()?),
+        ["vaults", id, rest @ ..] => {
+            let Ok(vault_id) = id.parse::<u64>() else {
+                return Ok(None);
+            };
+            let filter = Filter::Vault(vault_id);
+            match rest {
+                [] => match store.vault(vault_id)? {
+                    Some(vault) => json(&vault),
+                    None => return Ok(None),
+                },
+                ["positions"] => json(&store.positions(filter)?),
+                ["votes"] => json(&store.votes(filter)?),
+                ["deposits"] => json(&store.deposits(filter)?),
+                ["claims"] => json(&store.claims(filter)?),
+                ["updates"] => json(&store.vault_updates(vault_id)?),
+                _ => return Ok(None),
+            }
+        }
+        ["users", key, listing] => {
+            let Ok(user) = key.parse::<Pubkey>() else {
+                return Ok(None);
+            };
+            let filter = Filter::Account(user);
+            match *listing {
+                "positions" => json(&store.positions(filter)?),
+                "votes" => json(&store.votes(filter)?),
+                "claims" => json(&store.claims(filter)?),
+                _ => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(body?))
+}
+
+fn json(value: &impl Serialize) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn error(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
@@ -0,0 +1,733 @@
+//! Decodes dao-voting accounts, and instructions, token balance changes and
+//! events out of confirmed transactions.
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use dao_voting_client::dao_voting::{
+    self, instruction as ix, MaxVoteCapUpdated, VaultScheduleUpdated, VeVoteCast,
+};
+use solana_sdk::account::Account;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+
+/// How a user added to or took back their vote weight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteKind {
+    Burn,
+    Lock,
+    Ve,
+    Unlock,
+}
+
+impl VoteKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VoteKind::Burn => "burn",
+            VoteKind::Lock => "lock",
+            VoteKind::Ve => "ve",
+            VoteKind::Unlock => "unlock",
+        }
+    }
+}
+
+/// Vault activity recorded by one instruction or event. `vault` is always
+/// the vault account address; the indexer maps it to a vault id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Activity {
+    VaultCreated {
+        vault: Pubkey,
+    },
+    /// Any admin update to a vault's settings.
+    VaultUpdated {
+        vault: Pubkey,
+    },
+    Vote {
+        vault: Pubkey,
+        user: Pubkey,
+        kind: VoteKind,
+        amount: u64,
+    },
+    Deposit {
+        vault: Pubkey,
+        authority: Pubkey,
+        amount: u64,
+    },
+    Claim {
+        vault: Pubkey,
+        user: Pubkey,
+        amount: u64,
+        vote_tokens_burned: u64,
+    },
+    ScheduleUpdated {
+        vault: Pubkey,
+        old_deadline: i64,
+        new_deadline: i64,
+        convert_time: i64,
+    },
+    MaxVoteCapUpdated {
+        vault: Pubkey,
+        old_max_vote_cap: u128,
+        new_max_vote_cap: u128,
+    },
+}
+
+impl Activity {
+    pub fn vault(&self) -> &Pubkey {
+        match self {
+            Activity::VaultCreated { vault }
+            | Activity::VaultUpdated { vault }
+            | Activity::Vote { vault, .. }
+            | Activity::Deposit { vault, .. }
+            | Activity::Claim { vault, .. }
+            | Activity::ScheduleUpdated { vault, .. }
+            | Activity::MaxVoteCapUpdated { vault, .. } => vault,
+        }
+    }
+
+    /// The user whose position changed, if any.
+    pub fn user(&self) -> Option<&Pubkey> {
+        match self {
+            Activity::Vote { user, .. } | Activity::Claim { user, .. } => Some(user),
+            _ => None,
+        }
+    }
+}
+
+/// A top-level dao-voting instruction with its accounts resolved.
+struct ProgramInstruction<'a> {
+    data: &'a [u8],
+    accounts: Vec<(u8, Pubkey)>,
+}
+
+impl ProgramInstruction<'_> {
+    fn account(&self, position: usize) -> Option<Pubkey> {
+        self.accounts.get(position).map(|(_, key)| *key)
+    }
+
+    fn account_index(&self, position: usize) -> Option<u8> {
+        self.accounts.get(position).map(|(index, _)| *index)
+    }
+
+    fn args<T: AnchorDeserialize>(&self) -> Option<T> {
+        T::try_from_slice(&self.data[8..]).ok()
+    }
+}
+
+/// Everything dao-voting did in a successful transaction, in order:
+/// instructions first, then the events they logged. Failed transactions and
+/// unknown instructions decode to nothing.
+pub fn decode_transaction(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<Activity> {
+    let Some(meta) = &tx.transaction.meta else {
+        return Vec::new();
+    };
+    if meta.err.is_some() {
+        return Vec::new();
+    }
+    let Some(transaction) = tx.transaction.transaction.decode() else {
+        return Vec::new();
+    };
+
+    let mut keys = transaction.message.static_account_keys().to_vec();
+    if let (VersionedMessage::V0(_), OptionSerializer::Some(loaded)) =
+        (&transaction.message, &meta.loaded_addresses)
+    {
+        keys.extend(
+            loaded
+                .writable
+                .iter()
+                .chain(&loaded.readonly)
+                .filter_map(|key| key.parse::<Pubkey>().ok()),
+        );
+    }
+
+    let mut activity = Vec::new();
+    for compiled in transaction.message.instructions() {
+        if keys.get(compiled.program_id_index as usize) != Some(&dao_voting::ID) {
+            continue;
+        }
+        if compiled.data.len() < 8 {
+            continue;
+        }
+        let instruction = ProgramInstruction {
+            data: &compiled.data,
+            accounts: compiled
+                .accounts
+                .iter()
+                .filter_map(|&index| keys.get(index as usize).map(|key| (index, *key)))
+                .collect(),
+        };
+        activity.extend(decode_instruction(&instruction, meta));
+    }
+    if let OptionSerializer::Some(logs) = &meta.log_messages {
+        activity.extend(decode_events(logs));
+    }
+    activity
+}
+
+/// A dao-voting account, or `None` if another program owns it or it doesn't
+/// decode as `T`.
+pub fn decode_account<T: AccountDeserialize>(account: &Account) -> Option<T> {
+    if account.owner != dao_voting::ID {
+        return None;
+    }
+    T::try_deserialize(&mut account.data.as_slice()).ok()
+}
+
+fn decode_instruction(
+    instruction: &ProgramInstruction,
+    meta: &UiTransactionStatusMeta,
+) -> Option<Activity> {
+    let discriminator: [u8; 8] = instruction.data[..8].try_into().ok()?;
+    // Account positions follow the field order of each `Accounts` struct, as
+    // laid out by the dao-voting-client builders
+    let activity = match discriminator {
+        ix::CreateStAAndVault::DISCRIMINATOR => Activity::VaultCreated {
+            vault: instruction.account(2)?,
+        },
+        ix::SetProjectToken::DISCRIMINATOR
+        | ix::SetMerkleRoot::DISCRIMINATOR
+        | ix::UpdateVotingWindow::DISCRIMINATOR
+        | ix::UpdateVaultSchedule::DISCRIMINATOR
+        | ix::UpdateMaxVoteCap::DISCRIMINATOR => Activity::VaultUpdated {
+            vault: instruction.account(2)?,
+        },
+        ix::Vote::DISCRIMINATOR => Activity::Vote {
+            vault: instruction.account(3)?,
+            user: instruction.account(5)?,
+            kind: VoteKind::Burn,
+            amount: instruction.args::<ix::Vote>()?.amount,
+        },
+        ix::LockVote::DISCRIMINATOR => Activity::Vote {
+            vault: instruction.account(1)?,
+            user: instruction.account(6)?,
+            kind: VoteKind::Lock,
+            amount: instruction.args::<ix::LockVote>()?.amount,
+        },
+        // Unlocked amounts come from the program's state, so read them off
+        // the user's token account instead. Lock weight comes from `VeVoteCast`
+        ix::UnlockVote::DISCRIMINATOR => Activity::Vote {
+            vault: instruction.account(0)?,
+            user: instruction.account(5)?,
+            kind: VoteKind::Unlock,
+            amount: token_delta(meta, instruction.account_index(3)?).unsigned_abs(),
+        },
+        ix::DepositProjectTokens::DISCRIMINATOR => Activity::Deposit {
+            vault: instruction.account(2)?,
+            authority: instruction.account(6)?,
+            amount: instruction.args::<ix::DepositProjectTokens>()?.amount,
+        },
+        ix::ClaimProjectTokens::DISCRIMINATOR => Activity::Claim {
+            vault: instruction.account(1)?,
+            user: instruction.account(8)?,
+            amount: token_delta(meta, instruction.account_index(6)?).unsigned_abs(),
+            vote_tokens_burned: token_delta(meta, instruction.account_index(4)?).unsigned_abs(),
+        },
+        _ => return None,
+    };
+    Some(activity)
+}
+
+/// Change in the token balance of the account at `index` over the
+/// transaction. Accounts created by the transaction start from zero.
+fn token_delta(meta: &UiTransactionStatusMeta, index: u8) -> i64 {
+    let delta = token_balance(&meta.post_token_balances, index)
+        - token_balance(&meta.pre_token_balances, index);
+    delta.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+fn token_balance(balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>, index: u8) -> i128 {
+    let OptionSerializer::Some(balances) = balances else {
+        return 0;
+    };
+    balances
+        .iter()
+        .find(|balance| balance.account_index == index)
+        .and_then(|balance| balance.ui_token_amount.amount.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Decodes the events dao-voting logged. `Program data:` lines only count
+/// while dao-voting is the innermost running program, so data logged by
+/// programs it invokes, or by programs invoking it, is skipped.
+fn decode_events(logs: &[String]) -> Vec<Activity> {
+    let program_id = dao_voting::ID.to_string();
+    let mut stack = Vec::new();
+    let mut activity = Vec::new();
+    for log in logs {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if stack.last() == Some(&program_id.as_str()) {
+                activity.extend(decode_event(data));
+            }
+            continue;
+        }
+        // Track `Program <id> invoke [n]` and `Program <id> success` or
+        // `Program <id> failed: ...`
+        let Some(line) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        let mut words = line.split(' ');
+        match (words.next(), words.next()) {
+            (Some(id), Some("invoke")) => stack.push(id),
+            (Some(_), Some("success" | "failed:")) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    activity
+}
+
+/// Decodes an Anchor event from the base64 payload of a `Program data:` log.
+fn decode_event(data: &str) -> Option<Activity> {
+    let data = BASE64_STANDARD.decode(data).ok()?;
+    if data.len() < 8 {
+        return None;
+    }
+    let (discriminator, mut payload) = data.split_at(8);
+    let activity = match <[u8; 8]>::try_from(discriminator).ok()? {
+        VaultScheduleUpdated::DISCRIMINATOR => {
+            let event = VaultScheduleUpdated::deserialize(&mut payload).ok()?;
+            Activity::ScheduleUpdated {
+                vault: event.vault,
+                old_deadline: event.old_deadline,
+                new_deadline: event.new_deadline,
+                convert_time: event.convert_time,
+            }
+        }
+        VeVoteCast::DISCRIMINATOR => {
+            let event = VeVoteCast::deserialize(&mut payload).ok()?;
+            Activity::Vote {
+                vault: event.vault,
+                user: event.user,
+                kind: VoteKind::Ve,
+                amount: event.weight,
+            }
+        }
+        MaxVoteCapUpdated::DISCRIMINATOR => {
+            let event = MaxVoteCapUpdated::deserialize(&mut payload).ok()?;
+            Activity::MaxVoteCapUpdated {
+                vault: event.vault,
+                old_max_vote_cap: event.old_max_vote_cap,
+                new_max_vote_cap: event.new_max_vote_cap,
+            }
+        }
+        _ => return None,
+    };
+    Some(activity)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use anchor_lang::{AccountSerialize, Event};
+    use anchor_spl::associated_token::get_associated_token_address;
+    use dao_voting::{pda, ScheduleUpdate, TokenMetadata, UserVault, Vault, VaultMode};
+    use dao_voting_client::{
+        ClaimProjectTokensBuilder, CreateStAAndVaultBuilder, DepositProjectTokensBuilder,
+        LockVoteBuilder, SetMerkleRootBuilder, SetProjectTokenBuilder, UnlockVoteBuilder,
+        UpdateMaxVoteCapBuilder, UpdateVaultScheduleBuilder, UpdateVotingWindowBuilder,
+        VoteBuilder,
+    };
+    use serde_json::json;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::transaction::Transaction;
+    use solana_transaction_status::{
+        Encodable, EncodedTransactionWithStatusMeta, UiTransactionEncoding,
+    };
+
+    use super::*;
+
+    pub(crate) fn vault(vault_id: u64) -> Vault {
+        Vault {
+            owner: Pubkey::new_unique(),
+            governance_token_mint: Pubkey::new_unique(),
+            vote_token_mint: Pubkey::new_unique(),
+            project_token_mint: Pubkey::new_unique(),
+            convert_time: 1_700_086_400,
+            total_burned: 300,
+            total_deposited: 1_000,
+            vault_id,
+            max_vote_cap: u128::MAX,
+            deadline: 1_700_000_000,
+            st_governance_token_mint: Pubkey::new_unique(),
+            max_per_user: Some(500),
+            min_per_vote: None,
+            merkle_root: None,
+            voting_start: 1_699_000_000,
+            max_deadline_extension: 0,
+            deadline_extended: 0,
+            mode: VaultMode::Burn,
+            total_locked: 0,
+            total_ve_weight: 40,
+            version: dao_voting::VAULT_VERSION,
+            reserved: [0; 64],
+        }
+    }
+
+    fn program_account(account: &impl AccountSerialize) -> Account {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        Account {
+            lamports: 1_000_000,
+            data,
+            owner: dao_voting::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn data_log(event: &impl Event) -> String {
+        format!("Program data: {}", BASE64_STANDARD.encode(event.data()))
+    }
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn decodes_accounts_written_by_the_program() {
+        let vault = vault(7);
+        let mut account = program_account(&vault);
+        let decoded: Vault = decode_account(&account).unwrap();
+        assert_eq!(decoded.vault_id, 7);
+        assert_eq!(decoded.max_vote_cap, u128::MAX);
+        assert_eq!(decoded.max_per_user, Some(500));
+        assert_eq!(decoded.total_ve_weight, 40);
+        assert_eq!(decoded.project_token_mint, vault.project_token_mint);
+
+        // The discriminator tells account types apart
+        assert!(decode_account::<UserVault>(&account).is_none());
+
+        account.data.truncate(100);
+        assert!(decode_account::<Vault>(&account).is_none());
+
+        let mut foreign = program_account(&vault);
+        foreign.owner = Pubkey::new_unique();
+        assert!(decode_account::<Vault>(&foreign).is_none());
+    }
+
+    #[test]
+    fn decodes_events_logged_by_dao_voting() {
+        let vault = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let invoke = format!("Program {} invoke [1]", dao_voting::ID);
+        let success = format!("Program {} success", dao_voting::ID);
+        let lines = [
+            invoke.clone(),
+            data_log(&VeVoteCast {
+                vault,
+                user,
+                weight: 250,
+            }),
+            data_log(&MaxVoteCapUpdated {
+                vault,
+                old_max_vote_cap: 100,
+                new_max_vote_cap: u128::MAX,
+            }),
+            success.clone(),
+            invoke,
+            "Program log: Instruction: UpdateVaultSchedule".to_string(),
+            data_log(&VaultScheduleUpdated {
+                vault,
+                old_deadline: 10,
+                new_deadline: 20,
+                convert_time: 30,
+            }),
+            // Malformed payloads are skipped
+            "Program data: not-base64".to_string(),
+            format!("Program data: {}", BASE64_STANDARD.encode([1, 2, 3])),
+            success,
+        ];
+
+        assert_eq!(
+            decode_events(&lines),
+            vec![
+                Activity::Vote {
+                    vault,
+                    user,
+                    kind: VoteKind::Ve,
+                    amount: 250,
+                },
+                Activity::MaxVoteCapUpdated {
+                    vault,
+                    old_max_vote_cap: 100,
+                    new_max_vote_cap: u128::MAX,
+                },
+                Activity::ScheduleUpdated {
+                    vault,
+                    old_deadline: 10,
+                    new_deadline: 20,
+                    convert_time: 30,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_data_logged_by_other_programs() {
+        let vault = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let event = |new_max_vote_cap| {
+            data_log(&MaxVoteCapUpdated {
+                vault,
+                old_max_vote_cap: 0,
+                new_max_vote_cap,
+            })
+        };
+        let id = dao_voting::ID;
+        let lines = logs(&[
+            // Another program at the top level
+            &format!("Program {other} invoke [1]"),
+            &event(1),
+            &format!("Program {other} success"),
+            // Another program invoked by dao-voting, then dao-voting again
+            &format!("Program {id} invoke [1]"),
+            &format!("Program {other} invoke [2]"),
+            &event(2),
+            &format!("Program {other} consumed 100 of 200 compute units"),
+            &format!("Program {other} success"),
+            &event(3),
+            &format!("Program {id} success"),
+            // dao-voting invoked by another program
+            &format!("Program {other} invoke [1]"),
+            &format!("Program {id} invoke [2]"),
+            &event(4),
+            &format!("Program {id} success"),
+            &event(5),
+            // A failed inner call returns to the caller
+            &format!("Program {id} invoke [2]"),
+            &format!("Program {id} failed: custom program error: 0x1770"),
+            &event(6),
+            &format!("Program {other} success"),
+            &event(7),
+        ]);
+
+        let caps: Vec<u128> = decode_events(&lines)
+            .into_iter()
+            .map(|activity| match activity {
+                Activity::MaxVoteCapUpdated {
+                    new_max_vote_cap, ..
+                } => new_max_vote_cap,
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect();
+        assert_eq!(caps, vec![3, 4]);
+    }
+
+    /// `tx` as `getTransaction` returns it, with `meta` in its RPC JSON form.
+    fn confirmed(
+        tx: &Transaction,
+        meta: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 42,
+            transaction: EncodedTransactionWithStatusMeta {
+                transaction: tx.encode(UiTransactionEncoding::Base64),
+                meta: Some(serde_json::from_value(meta).unwrap()),
+                version: None,
+            },
+            block_time: Some(1_700_000_000),
+        }
+    }
+
+    fn token_balance(tx: &Transaction, account: &Pubkey, amount: u64) -> serde_json::Value {
+        let index = tx
+            .message
+            .account_keys
+            .iter()
+            .position(|key| key == account)
+            .unwrap();
+        json!({
+            "accountIndex": index,
+            "mint": Pubkey::new_unique().to_string(),
+            "uiTokenAmount": {
+                "uiAmount": amount as f64,
+                "decimals": 0,
+                "amount": amount.to_string(),
+                "uiAmountString": amount.to_string(),
+            },
+        })
+    }
+
+    #[test]
+    fn decodes_vote_and_claim_transactions() {
+        let user = Pubkey::new_unique();
+        let project_mint = Pubkey::new_unique();
+        let vote = VoteBuilder::new(user, 3, 150).instruction();
+        let claim = ClaimProjectTokensBuilder::new(user, 3, project_mint).instruction();
+        let user_vote_account = claim.accounts[4].pubkey;
+        let user_project_account = claim.accounts[6].pubkey;
+        let vault = claim.accounts[1].pubkey;
+        let tx = Transaction::new_with_payer(&[vote, claim], Some(&user));
+
+        let meta = json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "preTokenBalances": [token_balance(&tx, &user_vote_account, 150)],
+            "postTokenBalances": [
+                token_balance(&tx, &user_vote_account, 0),
+                token_balance(&tx, &user_project_account, 375),
+            ],
+            "logMessages": [],
+        });
+        assert_eq!(
+            decode_transaction(&confirmed(&tx, meta.clone())),
+            vec![
+                Activity::Vote {
+                    vault,
+                    user,
+                    kind: VoteKind::Burn,
+                    amount: 150,
+                },
+                Activity::Claim {
+                    vault,
+                    user,
+                    amount: 375,
+                    vote_tokens_burned: 150,
+                },
+            ]
+        );
+
+        let mut failed = meta;
+        failed["err"] = json!({ "InstructionError": [0, { "Custom": 6000 }] });
+        assert!(decode_transaction(&confirmed(&tx, failed)).is_empty());
+    }
+
+    /// Decodes a transaction holding just `instruction`, in which each
+    /// `(account, pre, post)` token balance changed.
+    fn decode_alone(
+        instruction: Instruction,
+        payer: &Pubkey,
+        balances: &[(Pubkey, u64, u64)],
+    ) -> Vec<Activity> {
+        let tx = Transaction::new_with_payer(&[instruction], Some(payer));
+        let meta = json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "preTokenBalances": balances
+                .iter()
+                .map(|(account, pre, _)| token_balance(&tx, account, *pre))
+                .collect::<Vec<_>>(),
+            "postTokenBalances": balances
+                .iter()
+                .map(|(account, _, post)| token_balance(&tx, account, *post))
+                .collect::<Vec<_>>(),
+            "logMessages": [],
+        });
+        decode_transaction(&confirmed(&tx, meta))
+    }
+
+    #[test]
+    fn recovers_keys_from_client_built_instructions() {
+        let owner = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let project_mint = Pubkey::new_unique();
+        let vault = pda::find_vault_address(3).0;
+        let vote_mint = pda::find_vote_token_mint_address(&vault).0;
+        let st_governance_mint = pda::find_st_governance_token_mint_address().0;
+        let metadata = TokenMetadata {
+            name: "Vault".to_string(),
+            symbol: "VLT".to_string(),
+            uri: "https://example.com/vault.json".to_string(),
+        };
+
+        let create = CreateStAAndVaultBuilder::new(owner, 3, metadata, 1_000, 0, 10)
+            .with_role()
+            .instruction();
+        assert_eq!(
+            decode_alone(create, &owner, &[]),
+            vec![Activity::VaultCreated { vault }]
+        );
+
+        for update in [
+            SetProjectTokenBuilder::new(owner, 3, project_mint, 20)
+                .with_role()
+                .instruction(),
+            SetMerkleRootBuilder::new(owner, 3, None)
+                .with_role()
+                .instruction(),
+            UpdateVotingWindowBuilder::new(owner, 3, 0, 10)
+                .with_role()
+                .instruction(),
+            UpdateVaultScheduleBuilder::new(owner, 3, ScheduleUpdate::CloseNow)
+                .with_role()
+                .instruction(),
+            UpdateMaxVoteCapBuilder::new(owner, 3, 2_000)
+                .with_role()
+                .instruction(),
+        ] {
+            assert_eq!(
+                decode_alone(update, &owner, &[]),
+                vec![Activity::VaultUpdated { vault }]
+            );
+        }
+
+        let vote = VoteBuilder::new(user, 3, 150).instruction();
+        let lock = LockVoteBuilder::new(user, 3, 70).instruction();
+        let unlock = UnlockVoteBuilder::new(user, 3).instruction();
+        let user_st_governance_account = get_associated_token_address(&user, &st_governance_mint);
+        assert_eq!(
+            decode_alone(vote, &user, &[]),
+            vec![Activity::Vote {
+                vault,
+                user,
+                kind: VoteKind::Burn,
+                amount: 150,
+            }]
+        );
+        assert_eq!(
+            decode_alone(lock, &user, &[]),
+            vec![Activity::Vote {
+                vault,
+                user,
+                kind: VoteKind::Lock,
+                amount: 70,
+            }]
+        );
+        assert_eq!(
+            decode_alone(unlock, &user, &[(user_st_governance_account, 30, 100)]),
+            vec![Activity::Vote {
+                vault,
+                user,
+                kind: VoteKind::Unlock,
+                amount: 70,
+            }]
+        );
+
+        let deposit = DepositProjectTokensBuilder::new(owner, 3, project_mint, 500)
+            .with_role()
+            .instruction();
+        assert_eq!(
+            decode_alone(deposit, &owner, &[]),
+            vec![Activity::Deposit {
+                vault,
+                authority: owner,
+                amount: 500,
+            }]
+        );
+
+        let claim = ClaimProjectTokensBuilder::new(user, 3, project_mint).instruction();
+        let balances = [
+            (get_associated_token_address(&user, &vote_mint), 150, 0),
+            (get_associated_token_address(&user, &project_mint), 0, 375),
+        ];
+        assert_eq!(
+            decode_alone(claim, &user, &balances),
+            vec![Activity::Claim {
+                vault,
+                user,
+                amount: 375,
+                vote_tokens_burned: 150,
+            }]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! `c30dao-indexer`: indexes dao-voting vault activity into SQLite and
+//! serves it to dashboards over HTTP.
+//!
+//! The indexer replays the program's transaction history from RPC, then
+//! polls for new transactions. Progress is kept in the database, so a
+//! restart resumes where it stopped.
+
+mod api;
+mod decode;
+mod store;
+mod sync;
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Parser;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::store::Store;
+use crate::sync::Indexer;
+
+#[derive(Parser)]
+#[command(
+    name = "c30dao-indexer",
+    version,
+    about = "Index C30DAO vault activity into SQLite"
+)]
+struct Cli {
+    /// JSON RPC endpoint, e.g. a local validator or a cluster URL.
+    #[arg(long, short = 'u', default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// SQLite database file, created if missing.
+    #[arg(long, default_value = "c30dao-index.sqlite")]
+    database: PathBuf,
+    /// Address the query API listens on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// Seconds between polls for new transactions.
+    #[arg(long, default_value_t = 2)]
+    poll_interval: u64,
+    /// Catch up with the chain and exit, without serving the API.
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+    let mut indexer = Indexer::new(rpc, Store::open(&cli.database)?);
+
+    if cli.once {
+        let count = indexer.sync()?;
+        println!("Indexed {count} transactions");
+        return Ok(());
+    }
+
+    let database = cli.database.clone();
+    let listen = cli.listen.clone();
+    thread::spawn(move || {
+        if let Err(err) = api::serve(&listen, &database) {
+            eprintln!("query API stopped: {err:#}");
+            std::process::exit(1);
+        }
+    });
+    println!("Serving the query API on http://{}", cli.listen);
+
+    loop {
+        // RPC hiccups shouldn't stop the indexer; the next poll retries
+        match indexer.sync() {
+            Ok(0) => {}
+            Ok(count) => println!("Indexed {count} transactions"),
+            Err(err) => eprintln!("sync failed: {err:#}"),
+        }
+        thread::sleep(Duration::from_secs(cli.poll_interval));
+    }
+}
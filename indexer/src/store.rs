@@ -0,0 +1,713 @@
+//! SQLite storage for indexed vault activity.
+//!
+//! Token amounts and totals are stored as decimal TEXT, since `u64` and
+//! `u128` values don't fit SQLite integers, and returned the same way so
+//! JavaScript dashboards don't lose precision.
+
+use std::path::Path;
+
+use anyhow::Result;
+use dao_voting_client::dao_voting::{UserVault, Vault, VaultMode};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::decode::Activity;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS vaults (
+    vault_id INTEGER PRIMARY KEY,
+    address TEXT NOT NULL UNIQUE,
+    mode TEXT NOT NULL,
+    vote_token_mint TEXT NOT NULL,
+    project_token_mint TEXT NOT NULL,
+    voting_start INTEGER NOT NULL,
+    deadline INTEGER NOT NULL,
+    convert_time INTEGER NOT NULL,
+    max_vote_cap TEXT NOT NULL,
+    total_burned TEXT NOT NULL,
+    total_locked TEXT NOT NULL,
+    total_ve_weight TEXT NOT NULL,
+    total_deposited TEXT NOT NULL,
+    updated_slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS positions (
+    vault_id INTEGER NOT NULL,
+    user TEXT NOT NULL,
+    burned TEXT NOT NULL,
+    locked TEXT NOT NULL,
+    ve_weight TEXT NOT NULL,
+    updated_slot INTEGER NOT NULL,
+    PRIMARY KEY (vault_id, user)
+);
+CREATE TABLE IF NOT EXISTS votes (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    vault_id INTEGER NOT NULL,
+    user TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS votes_by_vault ON votes (vault_id, slot);
+CREATE INDEX IF NOT EXISTS votes_by_user ON votes (user, slot);
+CREATE TABLE IF NOT EXISTS deposits (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    vault_id INTEGER NOT NULL,
+    authority TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS deposits_by_vault ON deposits (vault_id, slot);
+CREATE TABLE IF NOT EXISTS claims (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    vault_id INTEGER NOT NULL,
+    user TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    vote_tokens_burned TEXT NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS claims_by_vault ON claims (vault_id, slot);
+CREATE INDEX IF NOT EXISTS claims_by_user ON claims (user, slot);
+CREATE TABLE IF NOT EXISTS vault_updates (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    vault_id INTEGER NOT NULL,
+    field TEXT NOT NULL,
+    old_value TEXT NOT NULL,
+    new_value TEXT NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS vault_updates_by_vault ON vault_updates (vault_id, slot);
+";
+
+/// Where an activity happened, shared by every row it produces.
+pub struct Origin<'a> {
+    pub signature: &'a str,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct Cursor {
+    pub signature: String,
+    pub slot: u64,
+}
+
+#[derive(Serialize)]
+pub struct VaultRow {
+    pub vault_id: u64,
+    pub address: String,
+    pub mode: String,
+    pub vote_token_mint: String,
+    pub project_token_mint: String,
+    pub voting_start: i64,
+    pub deadline: i64,
+    pub convert_time: i64,
+    pub max_vote_cap: String,
+    pub total_burned: String,
+    pub total_locked: String,
+    pub total_ve_weight: String,
+    pub total_deposited: String,
+    pub updated_slot: u64,
+}
+
+#[derive(Serialize)]
+pub struct PositionRow {
+    pub vault_id: u64,
+    pub user: String,
+    pub burned: String,
+    pub locked: String,
+    pub ve_weight: String,
+    pub updated_slot: u64,
+}
+
+#[derive(Serialize)]
+pub struct VoteRow {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub vault_id: u64,
+    pub user: String,
+    pub kind: String,
+    pub amount: String,
+}
+
+#[derive(Serialize)]
+pub struct DepositRow {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub vault_id: u64,
+    pub authority: String,
+    pub amount: String,
+}
+
+#[derive(Serialize)]
+pub struct ClaimRow {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub vault_id: u64,
+    pub user: String,
+    pub amount: String,
+    pub vote_tokens_burned: String,
+}
+
+#[derive(Serialize)]
+pub struct VaultUpdateRow {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub vault_id: u64,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        // The API reads while the indexer writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn cursor(&self) -> Result<Option<Cursor>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT signature, slot FROM cursor WHERE id = 0",
+                [],
+                |row| {
+                    Ok(Cursor {
+                        signature: row.get(0)?,
+                        slot: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    pub fn vault_id(&self, address: &Pubkey) -> Result<Option<u64>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT vault_id FROM vaults WHERE address = ?1",
+                [address.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Stores a transaction's activity, with vault ids already resolved, and
+    /// moves the cursor past it. Replaying a transaction is a no-op.
+    pub fn record_transaction(
+        &mut self,
+        origin: &Origin,
+        activity: &[(u64, Activity)],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        let Origin {
+            signature,
+            slot,
+            block_time,
+        } = *origin;
+        for (seq, (vault_id, activity)) in activity.iter().enumerate() {
+            match activity {
+                Activity::VaultCreated { .. } | Activity::VaultUpdated { .. } => {}
+                Activity::Vote {
+                    user, kind, amount, ..
+                } => {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO votes VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        params![
+                            signature,
+                            seq,
+                            slot,
+                            block_time,
+                            vault_id,
+                            user.to_string(),
+                            kind.as_str(),
+                            amount.to_string()
+                        ],
+                    )?;
+                }
+                Activity::Deposit {
+                    authority, amount, ..
+                } => {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO deposits VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![
+                            signature,
+                            seq,
+                            slot,
+                            block_time,
+                            vault_id,
+                            authority.to_string(),
+                            amount.to_string()
+                        ],
+                    )?;
+                }
+                Activity::Claim {
+                    user,
+                    amount,
+                    vote_tokens_burned,
+                    ..
+                } => {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO claims VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        params![
+                            signature,
+                            seq,
+                            slot,
+                            block_time,
+                            vault_id,
+                            user.to_string(),
+                            amount.to_string(),
+                            vote_tokens_burned.to_string()
+                        ],
+                    )?;
+                }
+                Activity::ScheduleUpdated {
+                    old_deadline,
+                    new_deadline,
+                    ..
+                } => {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO vault_updates VALUES (?1, ?2, ?3, ?4, ?5, 'deadline', ?6, ?7)",
+                        params![
+                            signature,
+                            seq,
+                            slot,
+                            block_time,
+                            vault_id,
+                            old_deadline.to_string(),
+                            new_deadline.to_string()
+                        ],
+                    )?;
+                }
+                Activity::MaxVoteCapUpdated {
+                    old_max_vote_cap,
+                    new_max_vote_cap,
+                    ..
+                } => {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO vault_updates VALUES (?1, ?2, ?3, ?4, ?5, 'max_vote_cap', ?6, ?7)",
+                        params![
+                            signature,
+                            seq,
+                            slot,
+                            block_time,
+                            vault_id,
+                            old_max_vote_cap.to_string(),
+                            new_max_vote_cap.to_string()
+                        ],
+                    )?;
+                }
+            }
+        }
+        tx.execute(
+            "INSERT INTO cursor VALUES (0, ?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET signature = ?1, slot = ?2",
+            params![signature, slot],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn upsert_vault(&self, address: &Pubkey, vault: &Vault, slot: u64) -> Result<()> {
+        let mode = match vault.mode {
+            VaultMode::Burn => "burn",
+            VaultMode::Lock => "lock",
+        };
+        self.conn.execute(
+            "INSERT OR REPLACE INTO vaults
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                vault.vault_id,
+                address.to_string(),
+                mode,
+                vault.vote_token_mint.to_string(),
+                vault.project_token_mint.to_string(),
+                vault.voting_start,
+                vault.deadline,
+                vault.convert_time,
+                vault.max_vote_cap.to_string(),
+                vault.total_burned.to_string(),
+                vault.total_locked.to_string(),
+                vault.total_ve_weight.to_string(),
+                vault.total_deposited.to_string(),
+                slot
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn upsert_position(&self, vault_id: u64, user_vault: &UserVault, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO positions VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                vault_id,
+                user_vault.user.to_string(),
+                user_vault.burned_amount.to_string(),
+                user_vault.locked_amount.to_string(),
+                user_vault.ve_weight.to_string(),
+                slot
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn vaults(&self) -> Result<Vec<VaultRow>> {
+        self.query_vaults("SELECT * FROM vaults ORDER BY vault_id", [])
+    }
+
+    pub fn vault(&self, vault_id: u64) -> Result<Option<VaultRow>> {
+        Ok(self
+            .query_vaults("SELECT * FROM vaults WHERE vault_id = ?1", [vault_id])?
+            .pop())
+    }
+
+    fn query_vaults(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<VaultRow>> {
+        let mut statement = self.conn.prepare(sql)?;
+        let rows = statement.query_map(params, |row| {
+            Ok(VaultRow {
+                vault_id: row.get(0)?,
+                address: row.get(1)?,
+                mode: row.get(2)?,
+                vote_token_mint: row.get(3)?,
+                project_token_mint: row.get(4)?,
+                voting_start: row.get(5)?,
+                deadline: row.get(6)?,
+                convert_time: row.get(7)?,
+                max_vote_cap: row.get(8)?,
+                total_burned: row.get(9)?,
+                total_locked: row.get(10)?,
+                total_ve_weight: row.get(11)?,
+                total_deposited: row.get(12)?,
+                updated_slot: row.get(13)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Positions in a vault, or every position of a user.
+    pub fn positions(&self, filter: Filter) -> Result<Vec<PositionRow>> {
+        let (sql, value) = filter.sql("SELECT * FROM positions", "user");
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map([value], |row| {
+            Ok(PositionRow {
+                vault_id: row.get(0)?,
+                user: row.get(1)?,
+                burned: row.get(2)?,
+                locked: row.get(3)?,
+                ve_weight: row.get(4)?,
+                updated_slot: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn votes(&self, filter: Filter) -> Result<Vec<VoteRow>> {
+        let (sql, value) = filter.sql(
+            "SELECT signature, slot, block_time, vault_id, user, kind, amount FROM votes",
+            "user",
+        );
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map([value], |row| {
+            Ok(VoteRow {
+                signature: row.get(0)?,
+                slot: row.get(1)?,
+                block_time: row.get(2)?,
+                vault_id: row.get(3)?,
+                user: row.get(4)?,
+                kind: row.get(5)?,
+                amount: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn deposits(&self, filter: Filter) -> Result<Vec<DepositRow>> {
+        let (sql, value) = filter.sql(
+            "SELECT signature, slot, block_time, vault_id, authority, amount FROM deposits",
+            "authority",
+        );
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map([value], |row| {
+            Ok(DepositRow {
+                signature: row.get(0)?,
+                slot: row.get(1)?,
+                block_time: row.get(2)?,
+                vault_id: row.get(3)?,
+                authority: row.get(4)?,
+                amount: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn claims(&self, filter: Filter) -> Result<Vec<ClaimRow>> {
+        let (sql, value) = filter.sql(
+            "SELECT signature, slot, block_time, vault_id, user, amount, vote_tokens_burned FROM claims",
+            "user",
+        );
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map([value], |row| {
+            Ok(ClaimRow {
+                signature: row.get(0)?,
+                slot: row.get(1)?,
+                block_time: row.get(2)?,
+                vault_id: row.get(3)?,
+                user: row.get(4)?,
+                amount: row.get(5)?,
+                vote_tokens_burned: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn vault_updates(&self, vault_id: u64) -> Result<Vec<VaultUpdateRow>> {
+        let mut statement = self.conn.prepare(
+            "SELECT signature, slot, block_time, vault_id, field, old_value, new_value
+             FROM vault_updates WHERE vault_id = ?1 ORDER BY rowid",
+        )?;
+        let rows = statement.query_map([vault_id], |row| {
+            Ok(VaultUpdateRow {
+                signature: row.get(0)?,
+                slot: row.get(1)?,
+                block_time: row.get(2)?,
+                vault_id: row.get(3)?,
+                field: row.get(4)?,
+                old_value: row.get(5)?,
+                new_value: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// Narrows a listing to one vault or one account.
+pub enum Filter {
+    Vault(u64),
+    Account(Pubkey),
+}
+
+impl Filter {
+    /// Appends the filter to `select`, matching accounts against
+    /// `account_column`. Rows come back in the order they were indexed.
+    fn sql(&self, select: &str, account_column: &str) -> (String, Value) {
+        match self {
+            Filter::Vault(vault_id) => (
+                format!("{select} WHERE vault_id = ?1 ORDER BY rowid"),
+                Value::Integer(*vault_id as i64),
+            ),
+            Filter::Account(account) => (
+                format!("{select} WHERE {account_column} = ?1 ORDER BY rowid"),
+                Value::Text(account.to_string()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dao_voting_client::dao_voting::USER_VAULT_VERSION;
+
+    use super::*;
+    use crate::decode::tests::vault;
+    use crate::decode::VoteKind;
+
+    fn store() -> Store {
+        Store::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn origin(signature: &str, slot: u64) -> Origin<'_> {
+        Origin {
+            signature,
+            slot,
+            block_time: Some(1_700_000_000 + slot as i64),
+        }
+    }
+
+    #[test]
+    fn records_activity_and_moves_the_cursor() {
+        let mut store = store();
+        assert!(store.cursor().unwrap().is_none());
+
+        let vault = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let partner = Pubkey::new_unique();
+        let activity = vec![
+            (1, Activity::VaultCreated { vault }),
+            (
+                1,
+                Activity::Vote {
+                    vault,
+                    user,
+                    kind: VoteKind::Burn,
+                    amount: u64::MAX,
+                },
+            ),
+            (
+                1,
+                Activity::Deposit {
+                    vault,
+                    authority: partner,
+                    amount: 1_000,
+                },
+            ),
+            (
+                1,
+                Activity::MaxVoteCapUpdated {
+                    vault,
+                    old_max_vote_cap: 100,
+                    new_max_vote_cap: u128::MAX,
+                },
+            ),
+        ];
+        store
+            .record_transaction(&origin("first", 10), &activity)
+            .unwrap();
+        // Replays don't duplicate rows
+        store
+            .record_transaction(&origin("first", 10), &activity)
+            .unwrap();
+
+        let claim = Activity::Claim {
+            vault,
+            user,
+            amount: 375,
+            vote_tokens_burned: 150,
+        };
+        let schedule = Activity::ScheduleUpdated {
+            vault,
+            old_deadline: 10,
+            new_deadline: 20,
+            convert_time: 30,
+        };
+        store
+            .record_transaction(&origin("second", 11), &[(1, claim), (2, schedule)])
+            .unwrap();
+
+        let cursor = store.cursor().unwrap().unwrap();
+        assert_eq!((cursor.signature.as_str(), cursor.slot), ("second", 11));
+
+        let votes = store.votes(Filter::Vault(1)).unwrap();
+        assert_eq!(votes.len(), 1);
+        assert_eq!(votes[0].signature, "first");
+        assert_eq!(votes[0].kind, "burn");
+        assert_eq!(votes[0].amount, u64::MAX.to_string());
+        assert_eq!(votes[0].block_time, Some(1_700_000_010));
+        assert_eq!(store.votes(Filter::Account(user)).unwrap().len(), 1);
+        assert!(store.votes(Filter::Vault(2)).unwrap().is_empty());
+        assert!(store.votes(Filter::Account(partner)).unwrap().is_empty());
+
+        let deposits = store.deposits(Filter::Account(partner)).unwrap();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(
+            (deposits[0].vault_id, deposits[0].amount.as_str()),
+            (1, "1000")
+        );
+
+        let claims = store.claims(Filter::Account(user)).unwrap();
+        assert_eq!(claims.len(), 1);
+        assert_eq!(
+            (
+                claims[0].amount.as_str(),
+                claims[0].vote_tokens_burned.as_str()
+            ),
+            ("375", "150")
+        );
+
+        let updates = store.vault_updates(1).unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].field, "max_vote_cap");
+        assert_eq!(updates[0].new_value, u128::MAX.to_string());
+        let updates = store.vault_updates(2).unwrap();
+        assert_eq!(
+            (
+                updates[0].field.as_str(),
+                updates[0].old_value.as_str(),
+                updates[0].new_value.as_str()
+            ),
+            ("deadline", "10", "20")
+        );
+    }
+
+    #[test]
+    fn upserts_vaults_and_positions() {
+        let store = store();
+        let address = Pubkey::new_unique();
+        let mut vault = vault(4);
+        store.upsert_vault(&address, &vault, 10).unwrap();
+        assert_eq!(store.vault_id(&address).unwrap(), Some(4));
+        assert_eq!(store.vault_id(&Pubkey::new_unique()).unwrap(), None);
+
+        vault.mode = VaultMode::Lock;
+        vault.total_locked = 90;
+        store.upsert_vault(&address, &vault, 12).unwrap();
+        let row = store.vault(4).unwrap().unwrap();
+        assert_eq!(row.address, address.to_string());
+        assert_eq!(row.mode, "lock");
+        assert_eq!(row.max_vote_cap, u128::MAX.to_string());
+        assert_eq!(
+            (row.total_locked.as_str(), row.total_ve_weight.as_str()),
+            ("90", "40")
+        );
+        assert_eq!(row.updated_slot, 12);
+        assert_eq!(store.vaults().unwrap().len(), 1);
+        assert!(store.vault(5).unwrap().is_none());
+
+        let user = Pubkey::new_unique();
+        let mut user_vault = UserVault {
+            user,
+            vault: address,
+            burned_amount: 0,
+            locked_amount: 90,
+            ve_voted: true,
+            ve_weight: 40,
+            version: USER_VAULT_VERSION,
+            reserved: [0; 32],
+        };
+        store.upsert_position(4, &user_vault, 12).unwrap();
+        user_vault.locked_amount = 0;
+        store.upsert_position(4, &user_vault, 13).unwrap();
+
+        let positions = store.positions(Filter::Account(user)).unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(
+            (
+                positions[0].locked.as_str(),
+                positions[0].ve_weight.as_str()
+            ),
+            ("0", "40")
+        );
+        assert_eq!(positions[0].updated_slot, 13);
+        assert_eq!(store.positions(Filter::Vault(4)).unwrap().len(), 1);
+        assert!(store.positions(Filter::Vault(5)).unwrap().is_empty());
+    }
+}
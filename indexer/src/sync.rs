@@ -0,0 +1,150 @@
+//! Replays dao-voting transactions from RPC into the store.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use anchor_lang::AccountDeserialize;
+use anyhow::{anyhow, Context, Result};
+use dao_voting_client::dao_voting::{self, pda, UserVault, Vault};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::decode::{decode_account, decode_transaction};
+use crate::store::{Origin, Store};
+
+/// Signatures requested per `getSignaturesForAddress` page, the RPC maximum.
+const PAGE_SIZE: usize = 1_000;
+
+pub struct Indexer {
+    rpc: RpcClient,
+    store: Store,
+}
+
+impl Indexer {
+    pub fn new(rpc: RpcClient, store: Store) -> Self {
+        Self { rpc, store }
+    }
+
+    /// Indexes every program transaction after the cursor, oldest first,
+    /// then refreshes the vaults and positions they touched. Returns how
+    /// many transactions were processed.
+    pub fn sync(&mut self) -> Result<usize> {
+        let signatures = self.new_signatures()?;
+        let mut vaults = BTreeSet::new();
+        let mut positions = BTreeSet::new();
+
+        for (signature, slot) in &signatures {
+            let tx = self
+                .rpc
+                .get_transaction_with_config(
+                    signature,
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .with_context(|| format!("failed to fetch transaction {signature}"))?;
+
+            let mut activity = Vec::new();
+            for item in decode_transaction(&tx) {
+                let Some(vault_id) = self.resolve_vault(item.vault(), *slot)? else {
+                    eprintln!("skipping {item:?} in {signature}: no vault account");
+                    continue;
+                };
+                vaults.insert((vault_id, *item.vault()));
+                if let Some(user) = item.user() {
+                    positions.insert((vault_id, *item.vault(), *user));
+                }
+                activity.push((vault_id, item));
+            }
+
+            let signature = signature.to_string();
+            let origin = Origin {
+                signature: &signature,
+                slot: *slot,
+                block_time: tx.block_time,
+            };
+            self.store.record_transaction(&origin, &activity)?;
+        }
+
+        // Accounts are read at their current state, so a replay from
+        // genesis ends with the same snapshot as following along live
+        let slot = self.rpc.get_slot()?;
+        for (_, address) in &vaults {
+            if let Some(vault) = self.fetch::<Vault>(address)? {
+                self.store.upsert_vault(address, &vault, slot)?;
+            }
+        }
+        for (vault_id, vault, user) in &positions {
+            let address = pda::find_user_vault_address(vault, user).0;
+            if let Some(user_vault) = self.fetch::<UserVault>(&address)? {
+                self.store.upsert_position(*vault_id, &user_vault, slot)?;
+            }
+        }
+
+        Ok(signatures.len())
+    }
+
+    /// Signatures after the cursor, oldest first. Failed transactions are
+    /// included so the cursor moves past them.
+    fn new_signatures(&self) -> Result<Vec<(Signature, u64)>> {
+        let until = match self.store.cursor()? {
+            Some(cursor) => Some(Signature::from_str(&cursor.signature)?),
+            None => None,
+        };
+        let mut signatures = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self.rpc.get_signatures_for_address_with_config(
+                &dao_voting::ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(PAGE_SIZE),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )?;
+            let full = page.len() == PAGE_SIZE;
+            for status in page {
+                let signature = Signature::from_str(&status.signature)
+                    .map_err(|err| anyhow!("invalid signature {}: {err}", status.signature))?;
+                signatures.push((signature, status.slot));
+            }
+            match signatures.last() {
+                Some((last, _)) if full => before = Some(*last),
+                _ => break,
+            }
+        }
+        signatures.reverse();
+        Ok(signatures)
+    }
+
+    /// Vault id of the account at `address`, fetching and storing the vault
+    /// the first time it's seen. `None` if it isn't a vault.
+    fn resolve_vault(&mut self, address: &Pubkey, slot: u64) -> Result<Option<u64>> {
+        if let Some(vault_id) = self.store.vault_id(address)? {
+            return Ok(Some(vault_id));
+        }
+        let Some(vault) = self.fetch::<Vault>(address)? else {
+            return Ok(None);
+        };
+        self.store.upsert_vault(address, &vault, slot)?;
+        Ok(Some(vault.vault_id))
+    }
+
+    /// A program account, or `None` if it doesn't exist or doesn't decode
+    /// as `T`.
+    fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(address, CommitmentConfig::confirmed())
+            .with_context(|| format!("failed to fetch account {address}"))?
+            .value;
+        Ok(account.and_then(|account| decode_account(&account)))
+    }
+}
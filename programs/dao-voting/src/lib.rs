@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
 use anchor_spl::metadata::{
@@ -6,8 +7,39 @@ use anchor_spl::metadata::{
 };
 
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+pub mod pda;
+pub mod quote;
+
+use pda::{
+    CONVERSION_WINDOW_SEED, GLOBAL_STATE_SEED, GOVERNANCE_TOKEN_MINT_SEED, PROPOSAL_SEED,
+    PROPOSAL_VOTE_SEED, QUEUED_ACTION_SEED, ROLE_SEED, STAKE_SEED, ST_GOVERNANCE_TOKEN_MINT_SEED,
+    USER_VAULT_SEED, VAULT_SEED, VE_LOCK_SEED, VOTE_TOKEN_MINT_SEED,
+};
+
 declare_id!("6WvQ9rhpzkyxY62gqApZLmAtd52XRdo3McEJT5jEQ1Br");
 
+/// Longest governance token lock, which earns full voting weight.
+pub const MAX_VE_LOCK_DURATION: i64 = 4 * 365 * 24 * 60 * 60;
+
+/// Buckets in a `ConversionWindow` ring.
+pub const CONVERSION_WINDOW_SLOTS: usize = 9;
+
+/// Fixed-point scale of `GlobalState.reward_per_token_stored`.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Layout version of new `GlobalState` accounts. Version 0 is the layout
+/// from before accounts carried a version.
+pub const GLOBAL_STATE_VERSION: u8 = 1;
+
+/// Layout version of new `Vault` accounts. Version 0 is the layout from
+/// before accounts carried a version.
+pub const VAULT_VERSION: u8 = 1;
+
+/// Layout version of new `UserVault` accounts. Version 0 is the layout from
+/// before accounts carried a version.
+pub const USER_VAULT_VERSION: u8 = 1;
+
 #[program]
 pub mod dao_voting {
     use super::*;
@@ -16,14 +48,33 @@ pub mod dao_voting {
         ctx: Context<Initialize>,
         metadata: TokenMetadata,
         st_metadata: TokenMetadata,
+        timelock_delay: i64,
+        proposal_config: ProposalConfig,
     ) -> Result<()> {
+        require!(timelock_delay >= 0, ErrorCode::InvalidTimelockDelay);
+        proposal_config.validate()?;
+
         let global_state = &mut ctx.accounts.global_state;
         global_state.admin = ctx.accounts.admin.key();
         global_state.governance_token_mint = ctx.accounts.governance_token_mint.key();
         global_state.st_governance_token_mint = ctx.accounts.st_governance_token_mint.key();
+        global_state.vault_count = 0;
+        global_state.paused = false;
+        global_state.timelock_delay = timelock_delay;
+        global_state.queued_action_count = 0;
+        global_state.proposal_count = 0;
+        global_state.proposal_config = proposal_config;
+        global_state.total_staked = 0;
+        global_state.reward_per_token_stored = 0;
+        global_state.reward_rate = 0;
+        global_state.reward_period_end = 0;
+        global_state.rewards_last_updated = 0;
+        global_state.conversion_config = ConversionConfig::one_to_one(ctx.accounts.admin.key());
+        global_state.conversion_limits = ConversionLimits::UNLIMITED;
+        global_state.version = GLOBAL_STATE_VERSION;
 
         let governance_token_mint_seeds = &[
-            b"governance_token_mint".as_ref(),
+            GOVERNANCE_TOKEN_MINT_SEED,
             &[ctx.bumps.governance_token_mint],
         ];
         let signer = &[&governance_token_mint_seeds[..]];
@@ -61,7 +112,7 @@ pub mod dao_voting {
         msg!("Metadata URI: {}", metadata_account.uri);
 
         // Create metadata for st_governance token
-        let global_state_seeds = &[b"global_state".as_ref(), &[ctx.bumps.global_state]];
+        let global_state_seeds = &[GLOBAL_STATE_SEED, &[ctx.bumps.global_state]];
         let global_state_signer = &[&global_state_seeds[..]];
 
         let st_data = DataV2 {
@@ -93,10 +144,351 @@ pub mod dao_voting {
         Ok(())
     }
 
+    /// Creates the role granted by the queued `GrantRole` action `id` once
+    /// its timelock has passed. `payer` funds the role account and gets the
+    /// rent back when it is revoked.
+    pub fn grant_role(ctx: Context<GrantRole>, id: u64, kind: RoleKind) -> Result<()> {
+        let queued_action = &ctx.accounts.queued_action;
+
+        require!(
+            Clock::get()?.unix_timestamp >= queued_action.eta,
+            ErrorCode::TimelockNotExpired
+        );
+        let holder = ctx.accounts.holder.key();
+        require!(
+            queued_action.action == GovernanceAction::GrantRole { kind, holder },
+            ErrorCode::WrongAction
+        );
+
+        let role = &mut ctx.accounts.role;
+        role.holder = holder;
+        role.kind = kind;
+        role.granted_by = ctx.accounts.payer.key();
+
+        emit!(ActionExecuted { id });
+
+        Ok(())
+    }
+
+    /// Closes the role revoked by the queued `RevokeRole` action `id` once
+    /// its timelock has passed.
+    pub fn revoke_role(ctx: Context<RevokeRole>, id: u64) -> Result<()> {
+        let queued_action = &ctx.accounts.queued_action;
+
+        require!(
+            Clock::get()?.unix_timestamp >= queued_action.eta,
+            ErrorCode::TimelockNotExpired
+        );
+        let role = &ctx.accounts.role;
+        require!(
+            queued_action.action
+                == GovernanceAction::RevokeRole {
+                    kind: role.kind,
+                    holder: role.holder,
+                },
+            ErrorCode::WrongAction
+        );
+
+        emit!(ActionExecuted { id });
+
+        Ok(())
+    }
+
+    /// Emergency pause, effective immediately so a pauser can halt the
+    /// program without waiting out the timelock. Unpausing goes through it.
+    pub fn pause(ctx: Context<Pause>) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.pauser.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Pauser,
+        )?;
+
+        ctx.accounts.global_state.paused = true;
+        Ok(())
+    }
+
+    pub fn queue_action(ctx: Context<QueueAction>, action: GovernanceAction) -> Result<()> {
+        let proposer = ctx.accounts.proposer.key();
+        require_role(
+            &ctx.accounts.global_state,
+            &proposer,
+            ctx.accounts.role.as_ref(),
+            RoleKind::Admin,
+        )?;
+        action.validate()?;
+        // Only the global admin can hand out or take away the admin role
+        if let GovernanceAction::GrantRole {
+            kind: RoleKind::Admin,
+            ..
+        }
+        | GovernanceAction::RevokeRole {
+            kind: RoleKind::Admin,
+            ..
+        } = action
+        {
+            require_keys_eq!(
+                proposer,
+                ctx.accounts.global_state.admin,
+                ErrorCode::Unauthorized
+            );
+        }
+
+        enqueue_action(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.queued_action,
+            proposer,
+            action,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn execute_action(ctx: Context<ExecuteAction>, id: u64) -> Result<()> {
+        let queued_action = &ctx.accounts.queued_action;
+
+        require!(
+            Clock::get()?.unix_timestamp >= queued_action.eta,
+            ErrorCode::TimelockNotExpired
+        );
+
+        queued_action.action.apply(&mut ctx.accounts.global_state)?;
+
+        emit!(ActionExecuted { id });
+
+        Ok(())
+    }
+
+    pub fn cancel_action(ctx: Context<CancelAction>, id: u64) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.authority.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Admin,
+        )?;
+
+        emit!(ActionCancelled { id });
+
+        Ok(())
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: GovernanceAction) -> Result<()> {
+        action.validate()?;
+
+        let global_state = &mut ctx.accounts.global_state;
+        let id = global_state.proposal_count;
+        global_state.proposal_count = id.checked_add(1).ok_or(ErrorCode::ProposalCountOverflow)?;
+        let voting_end = Clock::get()?
+            .unix_timestamp
+            .checked_add(global_state.proposal_config.voting_period)
+            .ok_or(ErrorCode::InvalidProposalConfig)?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = id;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action.clone();
+        proposal.voting_end = voting_end;
+        proposal.yes_votes = 0;
+        proposal.no_votes = 0;
+        proposal.abstain_votes = 0;
+        proposal.quorum = global_state.proposal_config.quorum;
+        proposal.threshold_bps = global_state.proposal_config.threshold_bps;
+        proposal.executed = false;
+
+        emit!(ProposalCreated {
+            id,
+            proposer: proposal.proposer,
+            action,
+            voting_end,
+        });
+
+        Ok(())
+    }
+
+    pub fn cast_proposal_vote(
+        ctx: Context<CastProposalVote>,
+        id: u64,
+        choice: VoteChoice,
+        amount: u64,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(
+            Clock::get()?.unix_timestamp < proposal.voting_end,
+            ErrorCode::ProposalVotingEnded
+        );
+
+        let proposal_vote = &mut ctx.accounts.proposal_vote;
+        if proposal_vote.amount > 0 {
+            require!(
+                proposal_vote.choice == choice,
+                ErrorCode::VoteChoiceMismatch
+            );
+        }
+
+        // Lock st_governance tokens in the proposal escrow until voting ends
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx
+                        .accounts
+                        .voter_st_governance_token_account
+                        .to_account_info(),
+                    to: ctx.accounts.proposal_token_account.to_account_info(),
+                    authority: ctx.accounts.voter.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let tally = match choice {
+            VoteChoice::Yes => &mut proposal.yes_votes,
+            VoteChoice::No => &mut proposal.no_votes,
+            VoteChoice::Abstain => &mut proposal.abstain_votes,
+        };
+        *tally = tally.checked_add(amount).ok_or(ErrorCode::VoteOverflow)?;
+
+        proposal_vote.proposal = proposal.key();
+        proposal_vote.voter = ctx.accounts.voter.key();
+        proposal_vote.choice = choice;
+        proposal_vote.amount = proposal_vote
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::VoteOverflow)?;
+
+        emit!(ProposalVoteCast {
+            id,
+            voter: proposal_vote.voter,
+            choice,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_proposal_vote(ctx: Context<WithdrawProposalVote>, id: u64) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.proposal.voting_end,
+            ErrorCode::ProposalVotingActive
+        );
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.proposal_token_account.to_account_info(),
+                    to: ctx
+                        .accounts
+                        .voter_st_governance_token_account
+                        .to_account_info(),
+                    authority: ctx.accounts.proposal.to_account_info(),
+                },
+                &[&[
+                    PROPOSAL_SEED,
+                    id.to_le_bytes().as_ref(),
+                    &[ctx.bumps.proposal],
+                ]],
+            ),
+            ctx.accounts.proposal_vote.amount,
+        )?;
+
+        Ok(())
+    }
+
+    /// Queues the action of a passed proposal behind the timelock, like
+    /// `queue_action`. `execute_action` applies it once the delay is over,
+    /// and refunds the queued action's rent to `payer`.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>, id: u64) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(
+            Clock::get()?.unix_timestamp >= proposal.voting_end,
+            ErrorCode::ProposalVotingActive
+        );
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+
+        let total_votes =
+            proposal.yes_votes as u128 + proposal.no_votes as u128 + proposal.abstain_votes as u128;
+        require!(
+            total_votes >= proposal.quorum as u128,
+            ErrorCode::QuorumNotReached
+        );
+        // Abstentions count towards quorum but not towards the threshold
+        let decisive_votes = proposal.yes_votes as u128 + proposal.no_votes as u128;
+        require!(
+            proposal.yes_votes as u128 * 10_000 > decisive_votes * proposal.threshold_bps as u128,
+            ErrorCode::ProposalRejected
+        );
+
+        proposal.executed = true;
+
+        let queued_action_id = ctx.accounts.global_state.queued_action_count;
+        enqueue_action(
+            &mut ctx.accounts.global_state,
+            &mut ctx.accounts.queued_action,
+            ctx.accounts.payer.key(),
+            proposal.action.clone(),
+        )?;
+
+        emit!(ProposalExecuted {
+            id,
+            queued_action_id,
+        });
+
+        Ok(())
+    }
+
     pub fn convert_to_st_governance(
         ctx: Context<ConvertToStGovernance>,
         amount: u64,
     ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+
+        let conversion_config = ctx.accounts.global_state.conversion_config;
+        let (fee, net_amount, st_amount) = conversion_config.convert(amount)?;
+
+        // Count the conversion against the rolling window caps
+        let limits = ctx.accounts.global_state.conversion_limits;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.global_conversion_window.record(
+            now,
+            limits.window,
+            amount,
+            limits.global_cap,
+        )?;
+        ctx.accounts.user_conversion_window.record(
+            now,
+            limits.window,
+            amount,
+            limits.per_user_cap,
+        )?;
+
+        // Route the fee to the treasury
+        if fee > 0 {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_governance_token_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidTreasuryAccount)?;
+            require_keys_eq!(
+                treasury_token_account.owner,
+                conversion_config.treasury,
+                ErrorCode::InvalidTreasuryAccount
+            );
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.user_governance_token_account.to_account_info(),
+                        to: treasury_token_account.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+
         // Burn governance tokens
         token::burn(
             CpiContext::new(
@@ -107,7 +499,7 @@ pub mod dao_voting {
                     authority: ctx.accounts.user.to_account_info(),
                 },
             ),
-            amount,
+            net_amount,
         )?;
 
         // Mint st_governance tokens
@@ -122,23 +514,49 @@ pub mod dao_voting {
                         .to_account_info(),
                     authority: ctx.accounts.global_state.to_account_info(),
                 },
-                &[&[b"global_state".as_ref(), &[ctx.bumps.global_state]]],
+                &[&[GLOBAL_STATE_SEED, &[ctx.bumps.global_state]]],
             ),
-            amount,
+            st_amount,
         )?;
 
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_st_a_and_vault(
         ctx: Context<CreateStAAndVault>,
-        vault_id: u64,
         max_vote_cap: u128,
+        voting_start: i64,
         deadline: i64,
         metadata: TokenMetadata,
+        max_per_user: Option<u128>,
+        min_per_vote: Option<u64>,
+        merkle_root: Option<[u8; 32]>,
+        max_deadline_extension: i64,
+        mode: VaultMode,
     ) -> Result<()> {
+        require!(voting_start < deadline, ErrorCode::InvalidVotingWindow);
+        require!(
+            max_deadline_extension >= 0,
+            ErrorCode::InvalidScheduleUpdate
+        );
+
+        let creator = ctx.accounts.creator.key();
+        require_role(
+            &ctx.accounts.global_state,
+            &creator,
+            ctx.accounts.role.as_ref(),
+            RoleKind::Operator,
+        )?;
+
+        let global_state = &mut ctx.accounts.global_state;
+        let vault_id = global_state.vault_count;
+        global_state.vault_count = vault_id
+            .checked_add(1)
+            .ok_or(ErrorCode::VaultCountOverflow)?;
+
         let vault = &mut ctx.accounts.vault;
-        vault.owner = ctx.accounts.admin.key();
+        vault.owner = creator;
         vault.governance_token_mint = ctx.accounts.global_state.governance_token_mint;
         vault.st_governance_token_mint = ctx.accounts.global_state.st_governance_token_mint;
         vault.vote_token_mint = ctx.accounts.vote_token_mint.key();
@@ -148,10 +566,20 @@ pub mod dao_voting {
         vault.total_deposited = 0;
         vault.vault_id = vault_id;
         vault.max_vote_cap = max_vote_cap;
+        vault.voting_start = voting_start;
         vault.deadline = deadline;
+        vault.max_per_user = max_per_user;
+        vault.min_per_vote = min_per_vote;
+        vault.merkle_root = merkle_root;
+        vault.max_deadline_extension = max_deadline_extension;
+        vault.deadline_extended = 0;
+        vault.mode = mode;
+        vault.total_locked = 0;
+        vault.total_ve_weight = 0;
+        vault.version = VAULT_VERSION;
 
         let binding = vault_id.to_le_bytes();
-        let vault_seeds = &[b"vault", binding.as_ref(), &[ctx.bumps.vault]];
+        let vault_seeds = &[VAULT_SEED, binding.as_ref(), &[ctx.bumps.vault]];
         let vault_signer = &[&vault_seeds[..]];
 
         let data = DataV2 {
@@ -170,8 +598,8 @@ pub mod dao_voting {
                 metadata: ctx.accounts.metadata.to_account_info(),
                 mint: ctx.accounts.vote_token_mint.to_account_info(),
                 mint_authority: ctx.accounts.vault.to_account_info(),
-                payer: ctx.accounts.admin.to_account_info(),
-                update_authority: ctx.accounts.admin.to_account_info(),
+                payer: ctx.accounts.creator.to_account_info(),
+                update_authority: ctx.accounts.creator.to_account_info(),
                 system_program: ctx.accounts.system_program.to_account_info(),
                 rent: ctx.accounts.rent.to_account_info(),
             },
@@ -189,24 +617,37 @@ pub mod dao_voting {
         Ok(())
     }
 
-    pub fn vote(ctx: Context<Vote>, vault_id: u64, amount: u64) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
+    pub fn vote(
+        ctx: Context<Vote>,
+        vault_id: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+        allocation: Option<u64>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
 
-        // Check if voting is still allowed
-        require!(
-            Clock::get()?.unix_timestamp < vault.deadline,
-            ErrorCode::VotingEnded
-        );
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.mode == VaultMode::Burn, ErrorCode::WrongVaultMode);
 
-        // Check if the new total burned amount exceeds the max vote cap
+        // Lock weight cast with `ve_vote` counts towards the caps too
+        vault.check_vote(
+            quote::vault_vote_total(vault),
+            quote::user_vote_total(vault, &ctx.accounts.user_vault),
+            &ctx.accounts.user.key(),
+            amount,
+            &proof,
+            allocation,
+        )?;
         let new_total_burned = vault
             .total_burned
             .checked_add(amount as u128)
             .ok_or(ErrorCode::VoteOverflow)?;
-        require!(
-            new_total_burned <= vault.max_vote_cap,
-            ErrorCode::MaxVoteCapExceeded
-        );
+        let new_user_burned = ctx
+            .accounts
+            .user_vault
+            .burned_amount
+            .checked_add(amount as u128)
+            .ok_or(ErrorCode::VoteOverflow)?;
 
         // Burn st_governance tokens instead of governance tokens
         token::burn(
@@ -234,8 +675,8 @@ pub mod dao_voting {
                     authority: vault.to_account_info(),
                 },
                 &[&[
-                    b"vault",
-                    &vault_id.to_le_bytes().as_ref(),
+                    VAULT_SEED,
+                    vault_id.to_le_bytes().as_ref(),
                     &[ctx.bumps.vault],
                 ]],
             ),
@@ -244,12 +685,10 @@ pub mod dao_voting {
 
         // Update user vault
         let user_vault = &mut ctx.accounts.user_vault;
-        user_vault.burned_amount = user_vault
-            .burned_amount
-            .checked_add(amount as u128)
-            .ok_or(ErrorCode::VoteOverflow)?;
+        user_vault.burned_amount = new_user_burned;
         user_vault.user = ctx.accounts.user.key();
         user_vault.vault = vault.key();
+        user_vault.version = USER_VAULT_VERSION;
 
         // Update total votes in vault
         vault.total_burned = new_total_burned;
@@ -257,222 +696,1375 @@ pub mod dao_voting {
         Ok(())
     }
 
-    pub fn set_project_token(
-        ctx: Context<SetProjectToken>,
-        project_token_mint: Pubkey,
-        convert_time: i64,
-    ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        vault.project_token_mint = project_token_mint;
-        vault.convert_time = convert_time;
+    pub fn stake_st_governance(ctx: Context<StakeStGovernance>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_reward_per_token(Clock::get()?.unix_timestamp)?;
+        let stake_position = &mut ctx.accounts.stake_position;
+        stake_position.checkpoint(global_state.reward_per_token_stored)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx
+                        .accounts
+                        .owner_st_governance_token_account
+                        .to_account_info(),
+                    to: ctx.accounts.stake_token_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        stake_position.owner = ctx.accounts.owner.key();
+        stake_position.amount = stake_position
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::RewardOverflow)?;
+        global_state.total_staked = global_state
+            .total_staked
+            .checked_add(amount)
+            .ok_or(ErrorCode::RewardOverflow)?;
+
         Ok(())
     }
 
-    pub fn deposit_project_tokens(ctx: Context<DepositProjectTokens>, amount: u64) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
+    pub fn unstake_st_governance(ctx: Context<UnstakeStGovernance>, amount: u64) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_reward_per_token(Clock::get()?.unix_timestamp)?;
+        let stake_position = &mut ctx.accounts.stake_position;
+        stake_position.checkpoint(global_state.reward_per_token_stored)?;
 
         require!(
-            Clock::get()?.unix_timestamp < vault.convert_time,
-            ErrorCode::DepositNotAllowed
+            amount <= stake_position.amount,
+            ErrorCode::InsufficientStake
         );
+        stake_position.amount -= amount;
+        global_state.total_staked -= amount;
 
         token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 token::Transfer {
-                    from: ctx.accounts.project_token_account.to_account_info(),
-                    to: ctx.accounts.vault_token_account.to_account_info(),
-                    authority: ctx.accounts.project_authority.to_account_info(),
+                    from: ctx.accounts.stake_token_account.to_account_info(),
+                    to: ctx
+                        .accounts
+                        .owner_st_governance_token_account
+                        .to_account_info(),
+                    authority: ctx.accounts.global_state.to_account_info(),
                 },
+                &[&[GLOBAL_STATE_SEED, &[ctx.bumps.global_state]]],
             ),
             amount,
         )?;
 
-        vault.total_deposited = vault
-            .total_deposited
-            .checked_add(amount as u128)
-            .ok_or(ErrorCode::DepositOverflow)?;
-
         Ok(())
     }
 
-    pub fn claim_project_tokens(ctx: Context<ClaimProjectTokens>, vault_id: u64) -> Result<()> {
-        let vault = &ctx.accounts.vault;
-        let user_vault = &ctx.accounts.user_vault;
+    pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
 
-        require!(
-            Clock::get()?.unix_timestamp >= vault.convert_time,
-            ErrorCode::ClaimNotAvailable
-        );
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_reward_per_token(Clock::get()?.unix_timestamp)?;
+        let stake_position = &mut ctx.accounts.stake_position;
+        stake_position.checkpoint(global_state.reward_per_token_stored)?;
 
-        let user_ratio = (user_vault.burned_amount as f64) / (vault.total_burned as f64);
-        let claim_amount =
-            ((vault.total_deposited as f64 * user_ratio) as u128).min(u64::MAX as u128) as u64;
+        let rewards = stake_position.rewards_owed;
+        stake_position.rewards_owed = 0;
 
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 token::Transfer {
-                    from: ctx.accounts.vault_token_account.to_account_info(),
-                    to: ctx.accounts.user_project_token_account.to_account_info(),
-                    authority: ctx.accounts.vault.to_account_info(),
+                    from: ctx.accounts.reward_token_account.to_account_info(),
+                    to: ctx
+                        .accounts
+                        .owner_governance_token_account
+                        .to_account_info(),
+                    authority: ctx.accounts.global_state.to_account_info(),
                 },
-                &[&[
-                    b"vault",
-                    &vault_id.to_le_bytes().as_ref(),
-                    &[ctx.bumps.vault],
-                ]],
+                &[&[GLOBAL_STATE_SEED, &[ctx.bumps.global_state]]],
             ),
-            claim_amount,
+            rewards,
         )?;
 
-        let burn_amount = user_vault.burned_amount.min(u64::MAX as u128) as u64;
+        Ok(())
+    }
 
-        token::burn(
+    /// Streams `amount` governance tokens to stakers over `duration` seconds,
+    /// rolling any undistributed rewards into the new period.
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64, duration: i64) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.treasurer.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Treasurer,
+        )?;
+        require!(duration > 0, ErrorCode::InvalidRewardFunding);
+
+        let now = Clock::get()?.unix_timestamp;
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.update_reward_per_token(now)?;
+
+        let leftover = if now < global_state.reward_period_end {
+            (global_state.reward_period_end - now) as u128 * global_state.reward_rate as u128
+        } else {
+            0
+        };
+        let reward_rate = (amount as u128 + leftover) / duration as u128;
+        require!(reward_rate > 0, ErrorCode::InvalidRewardFunding);
+
+        global_state.reward_rate =
+            u64::try_from(reward_rate).map_err(|_| ErrorCode::RewardOverflow)?;
+        global_state.rewards_last_updated = now;
+        global_state.reward_period_end = now
+            .checked_add(duration)
+            .ok_or(ErrorCode::InvalidRewardFunding)?;
+
+        token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                token::Burn {
-                    mint: ctx.accounts.vote_token_mint.to_account_info(),
-                    from: ctx.accounts.user_vote_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
+                token::Transfer {
+                    from: ctx
+                        .accounts
+                        .treasurer_governance_token_account
+                        .to_account_info(),
+                    to: ctx.accounts.reward_token_account.to_account_info(),
+                    authority: ctx.accounts.treasurer.to_account_info(),
                 },
             ),
-            burn_amount,
+            amount,
         )?;
 
-        let user_vault = &mut ctx.accounts.user_vault;
-        user_vault.burned_amount = 0;
+        emit!(RewardsFunded {
+            amount,
+            duration,
+            reward_rate: ctx.accounts.global_state.reward_rate,
+        });
 
         Ok(())
     }
-}
+
+    pub fn create_ve_lock(ctx: Context<CreateVeLock>, amount: u64, duration: i64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+        require!(
+            amount > 0 && duration > 0 && duration <= MAX_VE_LOCK_DURATION,
+            ErrorCode::InvalidVeLock
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx
+                        .accounts
+                        .owner_governance_token_account
+                        .to_account_info(),
+                    to: ctx.accounts.ve_lock_token_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let ve_lock = &mut ctx.accounts.ve_lock;
+        ve_lock.owner = ctx.accounts.owner.key();
+        ve_lock.amount = amount;
+        ve_lock.lock_start = now;
+        ve_lock.unlock_time = now.checked_add(duration).ok_or(ErrorCode::InvalidVeLock)?;
+        ve_lock.weight_used = 0;
+
+        Ok(())
+    }
+
+    pub fn withdraw_ve_lock(ctx: Context<WithdrawVeLock>) -> Result<()> {
+        let ve_lock = &ctx.accounts.ve_lock;
+
+        require!(
+            Clock::get()?.unix_timestamp >= ve_lock.unlock_time,
+            ErrorCode::VeLockNotExpired
+        );
+
+        let owner = ctx.accounts.owner.key();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.ve_lock_token_account.to_account_info(),
+                    to: ctx
+                        .accounts
+                        .owner_governance_token_account
+                        .to_account_info(),
+                    authority: ctx.accounts.ve_lock.to_account_info(),
+                },
+                &[&[VE_LOCK_SEED, owner.as_ref(), &[ctx.bumps.ve_lock]]],
+            ),
+            ve_lock.amount,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn ve_vote(
+        ctx: Context<VeVote>,
+        _vault_id: u64,
+        proof: Vec<[u8; 32]>,
+        allocation: Option<u64>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.mode == VaultMode::Burn, ErrorCode::WrongVaultMode);
+        require!(
+            !ctx.accounts.user_vault.ve_voted,
+            ErrorCode::VeWeightAlreadyUsed
+        );
+
+        // A lock's weight is spent once, across every vault it votes in
+        let ve_lock = &mut ctx.accounts.ve_lock;
+        let weight = ve_lock
+            .voting_weight(Clock::get()?.unix_timestamp)
+            .saturating_sub(ve_lock.weight_used);
+        require!(weight > 0, ErrorCode::NoVotingWeight);
+
+        // Lock weight is tallied apart from burned votes: it costs nothing
+        // and earns no claim, so it neither fills the vote cap nor counts
+        // against per-user limits and allocations
+        vault.check_voting_open()?;
+        vault.check_allowlisted(&ctx.accounts.user.key(), &proof, allocation)?;
+
+        ve_lock.weight_used = ve_lock
+            .weight_used
+            .checked_add(weight)
+            .ok_or(ErrorCode::VoteOverflow)?;
+        vault.total_ve_weight = vault
+            .total_ve_weight
+            .checked_add(weight as u128)
+            .ok_or(ErrorCode::VoteOverflow)?;
+
+        let user_vault = &mut ctx.accounts.user_vault;
+        user_vault.ve_weight = user_vault
+            .ve_weight
+            .checked_add(weight as u128)
+            .ok_or(ErrorCode::VoteOverflow)?;
+        user_vault.ve_voted = true;
+        user_vault.user = ctx.accounts.user.key();
+        user_vault.vault = vault.key();
+        user_vault.version = USER_VAULT_VERSION;
+
+        emit!(VeVoteCast {
+            vault: vault.key(),
+            user: user_vault.user,
+            weight,
+        });
+
+        Ok(())
+    }
+
+    pub fn lock_vote(
+        ctx: Context<LockVote>,
+        _vault_id: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+        allocation: Option<u64>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.mode == VaultMode::Lock, ErrorCode::WrongVaultMode);
+
+        let (new_total_locked, new_user_locked) = vault.check_vote(
+            vault.total_locked,
+            ctx.accounts.user_vault.locked_amount,
+            &ctx.accounts.user.key(),
+            amount,
+            &proof,
+            allocation,
+        )?;
+
+        // Lock st_governance tokens in the vault escrow until the deadline
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx
+                        .accounts
+                        .user_st_governance_token_account
+                        .to_account_info(),
+                    to: ctx
+                        .accounts
+                        .vault_st_governance_token_account
+                        .to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let user_vault = &mut ctx.accounts.user_vault;
+        user_vault.locked_amount = new_user_locked;
+        user_vault.user = ctx.accounts.user.key();
+        user_vault.vault = vault.key();
+        user_vault.version = USER_VAULT_VERSION;
+
+        vault.total_locked = new_total_locked;
+
+        Ok(())
+    }
+
+    pub fn unlock_vote(ctx: Context<UnlockVote>, vault_id: u64) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+
+        require!(vault.mode == VaultMode::Lock, ErrorCode::WrongVaultMode);
+        require!(
+            Clock::get()?.unix_timestamp >= vault.deadline,
+            ErrorCode::VotingNotEnded
+        );
+
+        let locked_amount = ctx.accounts.user_vault.locked_amount;
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx
+                        .accounts
+                        .vault_st_governance_token_account
+                        .to_account_info(),
+                    to: ctx
+                        .accounts
+                        .user_st_governance_token_account
+                        .to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[&[
+                    VAULT_SEED,
+                    vault_id.to_le_bytes().as_ref(),
+                    &[ctx.bumps.vault],
+                ]],
+            ),
+            locked_amount.min(u64::MAX as u128) as u64,
+        )?;
+
+        // The vault keeps its total_locked as the final poll weight
+        ctx.accounts.user_vault.locked_amount = 0;
+
+        Ok(())
+    }
+
+    pub fn set_project_token(
+        ctx: Context<SetProjectToken>,
+        project_token_mint: Pubkey,
+        convert_time: i64,
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.owner.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Operator,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.mode == VaultMode::Burn, ErrorCode::WrongVaultMode);
+        require_claims_after_voting(convert_time, vault.deadline)?;
+
+        vault.project_token_mint = project_token_mint;
+        vault.convert_time = convert_time;
+        Ok(())
+    }
+
+    pub fn set_merkle_root(
+        ctx: Context<SetMerkleRoot>,
+        merkle_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.owner.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Operator,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        require!(
+            Clock::get()?.unix_timestamp < vault.voting_start,
+            ErrorCode::VotingAlreadyStarted
+        );
+
+        vault.merkle_root = merkle_root;
+        Ok(())
+    }
+
+    pub fn update_voting_window(
+        ctx: Context<UpdateVotingWindow>,
+        voting_start: i64,
+        deadline: i64,
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.owner.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Operator,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        require!(
+            Clock::get()?.unix_timestamp < vault.voting_start,
+            ErrorCode::VotingAlreadyStarted
+        );
+        require!(voting_start < deadline, ErrorCode::InvalidVotingWindow);
+        if vault.convert_time != 0 {
+            require_claims_after_voting(vault.convert_time, deadline)?;
+        }
+
+        vault.voting_start = voting_start;
+        vault.deadline = deadline;
+        Ok(())
+    }
+
+    pub fn update_vault_schedule(
+        ctx: Context<UpdateVaultSchedule>,
+        update: ScheduleUpdate,
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.owner.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Operator,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(now < vault.deadline, ErrorCode::VotingEnded);
+
+        let old_deadline = vault.deadline;
+        let new_deadline = match update {
+            ScheduleUpdate::Extend { seconds } => {
+                require!(seconds > 0, ErrorCode::InvalidScheduleUpdate);
+                let deadline_extended = vault
+                    .deadline_extended
+                    .checked_add(seconds)
+                    .ok_or(ErrorCode::InvalidScheduleUpdate)?;
+                require!(
+                    deadline_extended <= vault.max_deadline_extension,
+                    ErrorCode::MaxExtensionExceeded
+                );
+                vault.deadline_extended = deadline_extended;
+                old_deadline
+                    .checked_add(seconds)
+                    .ok_or(ErrorCode::InvalidScheduleUpdate)?
+            }
+            ScheduleUpdate::CloseNow => {
+                require!(now >= vault.voting_start, ErrorCode::VotingNotStarted);
+                now
+            }
+        };
+
+        // Keep the gap between the end of voting and claims unchanged
+        if vault.convert_time != 0 {
+            vault.convert_time = vault
+                .convert_time
+                .checked_add(new_deadline - old_deadline)
+                .ok_or(ErrorCode::InvalidScheduleUpdate)?;
+            require_claims_after_voting(vault.convert_time, new_deadline)?;
+        }
+        vault.deadline = new_deadline;
+
+        emit!(VaultScheduleUpdated {
+            vault: vault.key(),
+            old_deadline,
+            new_deadline,
+            convert_time: vault.convert_time,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_max_vote_cap(
+        ctx: Context<UpdateMaxVoteCap>,
+        new_max_vote_cap: u128,
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.owner.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::Operator,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        require!(
+            Clock::get()?.unix_timestamp < vault.deadline,
+            ErrorCode::VotingEnded
+        );
+        require!(
+            new_max_vote_cap >= quote::vault_vote_total(vault),
+            ErrorCode::MaxVoteCapBelowBurned
+        );
+
+        let old_max_vote_cap = vault.max_vote_cap;
+        vault.max_vote_cap = new_max_vote_cap;
+
+        emit!(MaxVoteCapUpdated {
+            vault: vault.key(),
+            old_max_vote_cap,
+            new_max_vote_cap,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_project_tokens(ctx: Context<DepositProjectTokens>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+        require_role(
+            &ctx.accounts.global_state,
+            &ctx.accounts.project_authority.key(),
+            ctx.accounts.role.as_ref(),
+            RoleKind::ProjectPartner,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        require!(
+            Clock::get()?.unix_timestamp < vault.convert_time,
+            ErrorCode::DepositNotAllowed
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.project_token_account.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.project_authority.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        vault.total_deposited = vault
+            .total_deposited
+            .checked_add(amount as u128)
+            .ok_or(ErrorCode::DepositOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn claim_project_tokens(ctx: Context<ClaimProjectTokens>, vault_id: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+
+        let vault = &ctx.accounts.vault;
+        let user_vault = &ctx.accounts.user_vault;
+
+        // Lock-mode votes are returned by `unlock_vote` and pay nothing out
+        require!(vault.mode == VaultMode::Burn, ErrorCode::WrongVaultMode);
+        require!(
+            Clock::get()?.unix_timestamp >= vault.convert_time,
+            ErrorCode::ClaimNotAvailable
+        );
+
+        let claim = quote::quote_claim(vault, user_vault);
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.user_project_token_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[&[
+                    VAULT_SEED,
+                    vault_id.to_le_bytes().as_ref(),
+                    &[ctx.bumps.vault],
+                ]],
+            ),
+            claim.claim_amount,
+        )?;
+
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.vote_token_mint.to_account_info(),
+                    from: ctx.accounts.user_vote_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            claim.vote_tokens_burned,
+        )?;
+
+        let user_vault = &mut ctx.accounts.user_vault;
+        user_vault.burned_amount = 0;
+
+        Ok(())
+    }
+
+    pub fn quote_claim(ctx: Context<QuoteClaim>, _vault_id: u64) -> Result<quote::ClaimQuote> {
+        Ok(quote::quote_claim(
+            &ctx.accounts.vault,
+            &ctx.accounts.user_vault,
+        ))
+    }
+
+    pub fn quote_vote(
+        ctx: Context<QuoteVote>,
+        _vault_id: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+        allocation: Option<u64>,
+    ) -> Result<quote::VoteQuote> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::Paused);
+
+        let vault = &ctx.accounts.vault;
+        let user_total = ctx
+            .accounts
+            .user_vault
+            .as_ref()
+            .map_or(0, |user_vault| quote::user_vote_total(vault, user_vault));
+        let (new_vault_total, new_user_total) = vault.check_vote(
+            quote::vault_vote_total(vault),
+            user_total,
+            &ctx.accounts.user.key(),
+            amount,
+            &proof,
+            allocation,
+        )?;
+
+        Ok(quote::VoteQuote {
+            new_vault_total,
+            new_user_total,
+            remaining_vote_capacity: vault.max_vote_cap - new_vault_total,
+        })
+    }
+
+    pub fn vault_summary(ctx: Context<ViewVault>, _vault_id: u64) -> Result<quote::VaultSummary> {
+        Ok(quote::vault_summary(
+            &ctx.accounts.vault,
+            Clock::get()?.unix_timestamp,
+        ))
+    }
+
+    /// Brings a global state written under an older layout up to
+    /// `GLOBAL_STATE_VERSION`. Anyone can migrate: it only appends fields,
+    /// zeroed apart from the conversion defaults, at the payer's expense.
+    pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+        migrate_account(
+            &ctx.accounts.global_state,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            |global_state: &mut GlobalState| {
+                require!(
+                    global_state.version < GLOBAL_STATE_VERSION,
+                    ErrorCode::AlreadyMigrated
+                );
+                // Layouts from before the conversion settings existed
+                // decode them as zero, which can't convert; start those
+                // from the `initialize` defaults
+                if global_state.conversion_config.rate_denominator == 0 {
+                    global_state.conversion_config =
+                        ConversionConfig::one_to_one(global_state.admin);
+                }
+                if global_state.conversion_limits.window == 0 {
+                    global_state.conversion_limits = ConversionLimits::UNLIMITED;
+                }
+                global_state.version = GLOBAL_STATE_VERSION;
+                Ok(())
+            },
+        )
+    }
+
+    /// Brings a vault written under an older layout up to `VAULT_VERSION`.
+    /// Anyone can migrate: it only appends zeroed fields, at the payer's
+    /// expense.
+    pub fn migrate_vault(ctx: Context<MigrateVault>, _vault_id: u64) -> Result<()> {
+        migrate_account(
+            &ctx.accounts.vault,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            |vault: &mut Vault| {
+                require!(vault.version < VAULT_VERSION, ErrorCode::AlreadyMigrated);
+                vault.version = VAULT_VERSION;
+                Ok(())
+            },
+        )
+    }
+
+    /// Brings a user vault written under an older layout up to
+    /// `USER_VAULT_VERSION`. Anyone can migrate: it only appends zeroed
+    /// fields, at the payer's expense.
+    pub fn migrate_user_vault(ctx: Context<MigrateUserVault>, _vault_id: u64) -> Result<()> {
+        migrate_account(
+            &ctx.accounts.user_vault,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            |user_vault: &mut UserVault| {
+                require!(
+                    user_vault.version < USER_VAULT_VERSION,
+                    ErrorCode::AlreadyMigrated
+                );
+                user_vault.version = USER_VAULT_VERSION;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + GlobalState::INIT_SPACE,
+        seeds = [GLOBAL_STATE_SEED],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        seeds = [GOVERNANCE_TOKEN_MINT_SEED],
+        bump,
+        payer = admin,
+        mint::decimals = 6,
+        mint::authority = admin,
+    )]
+    pub governance_token_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        seeds = [ST_GOVERNANCE_TOKEN_MINT_SEED],
+        bump,
+        payer = admin,
+        mint::decimals = 6,
+        mint::authority = global_state,
+    )]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: New Metaplex Account being created
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Metaplex program ID
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: New Metaplex Account being created for st_governance token
+    #[account(mut)]
+    pub st_metadata: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64, kind: RoleKind)]
+pub struct GrantRole<'info> {
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [QUEUED_ACTION_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    /// CHECK: Receives the queued action's rent, checked against `queued_action.proposer`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Role::INIT_SPACE,
+        seeds = [ROLE_SEED, &[kind as u8], holder.key().as_ref()],
+        bump
+    )]
+    pub role: Account<'info, Role>,
+    /// CHECK: Only used as the key the role is granted to
+    pub holder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct RevokeRole<'info> {
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [QUEUED_ACTION_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    /// CHECK: Receives the queued action's rent, checked against `queued_action.proposer`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    #[account(mut, close = granted_by)]
+    pub role: Account<'info, Role>,
+    /// Paid the role's rent when granting it, and gets it back
+    #[account(mut, address = role.granted_by)]
+    pub granted_by: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Pause<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    pub pauser: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueueAction<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + QueuedAction::INIT_SPACE,
+        seeds = [QUEUED_ACTION_SEED, global_state.queued_action_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct ExecuteAction<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [QUEUED_ACTION_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    /// CHECK: Receives the queued action's rent, checked against `queued_action.proposer`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CancelAction<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [QUEUED_ACTION_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    /// CHECK: Receives the queued action's rent, checked against `queued_action.proposer`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [PROPOSAL_SEED, global_state.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CastProposalVote<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [PROPOSAL_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + ProposalVote::INIT_SPACE,
+        seeds = [PROPOSAL_VOTE_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub proposal_vote: Account<'info, ProposalVote>,
+    #[account(address = global_state.st_governance_token_mint)]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub voter_st_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = voter,
+        associated_token::mint = st_governance_token_mint,
+        associated_token::authority = proposal
+    )]
+    pub proposal_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+#[instruction(id: u64)]
+pub struct WithdrawProposalVote<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        seeds = [PROPOSAL_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        close = voter,
+        has_one = proposal,
+        has_one = voter,
+        seeds = [PROPOSAL_VOTE_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub proposal_vote: Account<'info, ProposalVote>,
+    #[account(address = global_state.st_governance_token_mint)]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub voter_st_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = st_governance_token_mint,
+        associated_token::authority = proposal
+    )]
+    pub proposal_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [PROPOSAL_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
     #[account(
         init,
-        payer = admin,
-        space = 8 + 32 + 32 + 32,
-        seeds = [b"global_state"],
+        payer = payer,
+        space = 8 + QueuedAction::INIT_SPACE,
+        seeds = [QUEUED_ACTION_SEED, global_state.queued_action_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateStAAndVault<'info> {
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
         bump
     )]
     pub global_state: Account<'info, GlobalState>,
 
+    pub role: Option<Account<'info, Role>>,
+
     #[account(
         init,
-        seeds = [b"governance_token_mint"],
-        bump,
-        payer = admin,
-        mint::decimals = 6,
-        mint::authority = admin,
+        payer = creator,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [VAULT_SEED, global_state.vault_count.to_le_bytes().as_ref()],
+        bump
     )]
-    pub governance_token_mint: Account<'info, Mint>,
+    pub vault: Account<'info, Vault>,
+
     #[account(
         init,
-        seeds = [b"st_governance_token_mint"],
+        seeds = [VOTE_TOKEN_MINT_SEED, vault.key().as_ref()],
         bump,
-        payer = admin,
+        payer = creator,
         mint::decimals = 6,
-        mint::authority = global_state,
+        mint::authority = vault,
     )]
-    pub st_governance_token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub admin: Signer<'info>,
+    pub vote_token_mint: Account<'info, Mint>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub rent: Sysvar<'info, Rent>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
     /// CHECK: New Metaplex Account being created
     #[account(mut)]
     pub metadata: UncheckedAccount<'info>,
     /// CHECK: Metaplex program ID
     pub token_metadata_program: UncheckedAccount<'info>,
-    /// CHECK: New Metaplex Account being created for st_governance token
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct Vote<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(mut)]
-    pub st_metadata: UncheckedAccount<'info>,
+    pub vote_token_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vote_token_mint,
+        associated_token::authority = user
+    )]
+    pub user_vote_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserVault::INIT_SPACE,
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    #[account(mut, address = global_state.st_governance_token_mint)]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub user_st_governance_token_account: Account<'info, TokenAccount>,
 }
 
 #[derive(Accounts)]
 #[instruction(vault_id: u64)]
-pub struct CreateStAAndVault<'info> {
+pub struct LockVote<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(
-        init,
-        payer = admin,
-        space = 8 + 32 + 32 + 32 + 32 + 32 + 8 + 16 + 16 + 8 + 16 + 8,
-        seeds = [b"vault", vault_id.to_le_bytes().as_ref()],
+        mut,
+        seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserVault::INIT_SPACE,
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    #[account(address = vault.st_governance_token_mint)]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub user_st_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = st_governance_token_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_st_governance_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct UnlockVote<'info> {
+    #[account(
+        seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()],
         bump
     )]
     pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        has_one = user,
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    #[account(address = vault.st_governance_token_mint)]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub user_st_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = st_governance_token_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_st_governance_token_account: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
 
+#[derive(Accounts)]
+pub struct StakeStGovernance<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(
-        seeds = [b"global_state"],
-        bump,
-        has_one = admin
+        init_if_needed,
+        payer = owner,
+        space = 8 + StakePosition::INIT_SPACE,
+        seeds = [STAKE_SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    #[account(address = global_state.st_governance_token_mint)]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub owner_st_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = st_governance_token_mint,
+        associated_token::authority = global_state
     )]
+    pub stake_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeStGovernance<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [STAKE_SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    #[account(address = global_state.st_governance_token_mint)]
+    pub st_governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub owner_st_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = st_governance_token_mint,
+        associated_token::authority = global_state
+    )]
+    pub stake_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
 
+#[derive(Accounts)]
+pub struct ClaimStakingRewards<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(
-        init,
-        seeds = [b"vote_token_mint", vault.key().as_ref()],
-        bump,
-        payer = admin,
-        mint::decimals = 6,
-        mint::authority = vault,
+        mut,
+        has_one = owner,
+        seeds = [STAKE_SEED, owner.key().as_ref()],
+        bump
     )]
-    pub vote_token_mint: Account<'info, Mint>,
+    pub stake_position: Account<'info, StakePosition>,
+    #[account(address = global_state.governance_token_mint)]
+    pub governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub owner_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = governance_token_mint,
+        associated_token::authority = global_state
+    )]
+    pub reward_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
 
-    pub system_program: Program<'info, System>,
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    #[account(address = global_state.governance_token_mint)]
+    pub governance_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub treasurer_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = treasurer,
+        associated_token::mint = governance_token_mint,
+        associated_token::authority = global_state
+    )]
+    pub reward_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub treasurer: Signer<'info>,
     pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
 
+#[derive(Accounts)]
+pub struct CreateVeLock<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VeLock::INIT_SPACE,
+        seeds = [VE_LOCK_SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub ve_lock: Account<'info, VeLock>,
+    #[account(address = global_state.governance_token_mint)]
+    pub governance_token_mint: Account<'info, Mint>,
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub owner_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = governance_token_mint,
+        associated_token::authority = ve_lock
+    )]
+    pub ve_lock_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
 
-    /// CHECK: New Metaplex Account being created
+#[derive(Accounts)]
+pub struct WithdrawVeLock<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [VE_LOCK_SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub ve_lock: Account<'info, VeLock>,
+    #[account(address = global_state.governance_token_mint)]
+    pub governance_token_mint: Account<'info, Mint>,
     #[account(mut)]
-    pub metadata: UncheckedAccount<'info>,
-    /// CHECK: Metaplex program ID
-    pub token_metadata_program: UncheckedAccount<'info>,
+    pub owner_governance_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = governance_token_mint,
+        associated_token::authority = ve_lock
+    )]
+    pub ve_lock_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 #[instruction(vault_id: u64)]
-pub struct Vote<'info> {
-    #[account(mut)]
-    pub vote_token_mint: Account<'info, Mint>,
-    #[account(
-        init_if_needed,
-        payer = user,
-        associated_token::mint = vote_token_mint,
-        associated_token::authority = user
-    )]
-    pub user_vote_token_account: Account<'info, TokenAccount>,
+pub struct VeVote<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(
         mut,
-        seeds = [b"vault", vault_id.to_le_bytes().as_ref()],
+        seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()],
         bump
     )]
     pub vault: Account<'info, Vault>,
     #[account(
         init_if_needed,
         payer = user,
-        space = 8 + 32 + 32 + 16,
-        seeds = [b"user_vault", vault.key().as_ref(), user.key().as_ref()],
+        space = 8 + UserVault::INIT_SPACE,
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
         bump
     )]
     pub user_vault: Account<'info, UserVault>,
+    #[account(mut, seeds = [VE_LOCK_SEED, user.key().as_ref()], bump)]
+    pub ve_lock: Account<'info, VeLock>,
     #[account(mut)]
     pub user: Signer<'info>,
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    #[account(mut)]
-    pub st_governance_token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub user_st_governance_token_account: Account<'info, TokenAccount>,
 }
 
 #[derive(Accounts)]
 pub struct SetProjectToken<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMerkleRoot<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVotingWindow<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVaultSchedule<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
+    #[account(mut, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMaxVoteCap<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
     #[account(mut, has_one = owner)]
     pub vault: Account<'info, Vault>,
     pub owner: Signer<'info>,
@@ -480,6 +2072,9 @@ pub struct SetProjectToken<'info> {
 
 #[derive(Accounts)]
 pub struct DepositProjectTokens<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    pub role: Option<Account<'info, Role>>,
     #[account(mut)]
     pub vault: Account<'info, Vault>,
     #[account(mut)]
@@ -491,6 +2086,7 @@ pub struct DepositProjectTokens<'info> {
         associated_token::authority = vault
     )]
     pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(address = vault.project_token_mint)]
     pub project_token_mint: Account<'info, Mint>,
     #[account(mut)]
     pub project_authority: Signer<'info>,
@@ -503,9 +2099,11 @@ pub struct DepositProjectTokens<'info> {
 #[derive(Accounts)]
 #[instruction(vault_id: u64)]
 pub struct ClaimProjectTokens<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
     #[account(
         mut,
-        seeds = [b"vault", vault_id.to_le_bytes().as_ref()],
+        seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()],
         bump
     )]
     pub vault: Account<'info, Vault>,
@@ -528,7 +2126,7 @@ pub struct ClaimProjectTokens<'info> {
     #[account(
         mut,
         has_one = user,
-        seeds = [b"user_vault", vault.key().as_ref(), user.key().as_ref()],
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
         bump
     )]
     pub user_vault: Account<'info, UserVault>,
@@ -540,10 +2138,94 @@ pub struct ClaimProjectTokens<'info> {
 }
 
 #[derive(Accounts)]
-pub struct ConvertToStGovernance<'info> {
+#[instruction(vault_id: u64)]
+pub struct QuoteClaim<'info> {
+    #[account(seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()], bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        has_one = user,
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    /// CHECK: Only used to derive the user vault
+    pub user: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct QuoteVote<'info> {
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()], bump)]
+    pub vault: Account<'info, Vault>,
+    /// Omitted before the user's first vote in this vault.
+    #[account(
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_vault: Option<Account<'info, UserVault>>,
+    /// CHECK: Only used to derive the user vault and check the allowlist
+    pub user: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct ViewVault<'info> {
+    #[account(seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()], bump)]
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    /// CHECK: May not decode under the current layout; `migrate_account`
+    /// checks the owner and decodes it after the realloc
+    #[account(mut, seeds = [GLOBAL_STATE_SEED], bump)]
+    pub global_state: UncheckedAccount<'info>,
     #[account(mut)]
-    pub governance_token_mint: Account<'info, Mint>,
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct MigrateVault<'info> {
+    /// CHECK: May not decode under the current layout; `migrate_account`
+    /// checks the owner and decodes it after the realloc
+    #[account(mut, seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct MigrateUserVault<'info> {
+    /// CHECK: Only derives the user vault address, and may itself still be
+    /// on an older layout
+    #[account(seeds = [VAULT_SEED, vault_id.to_le_bytes().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: Only derives the user vault address
+    pub user: UncheckedAccount<'info>,
+    /// CHECK: May not decode under the current layout; `migrate_account`
+    /// checks the owner and decodes it after the realloc
+    #[account(
+        mut,
+        seeds = [USER_VAULT_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_vault: UncheckedAccount<'info>,
     #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConvertToStGovernance<'info> {
+    #[account(mut, address = global_state.governance_token_mint)]
+    pub governance_token_mint: Account<'info, Mint>,
+    #[account(mut, address = global_state.st_governance_token_mint)]
     pub st_governance_token_mint: Account<'info, Mint>,
     #[account(mut)]
     pub user_governance_token_account: Account<'info, TokenAccount>,
@@ -556,22 +2238,84 @@ pub struct ConvertToStGovernance<'info> {
     pub user_st_governance_token_account: Account<'info, TokenAccount>,
     #[account(mut)]
     pub user: Signer<'info>,
-    #[account(seeds = [b"global_state"], bump)]
+    #[account(seeds = [GLOBAL_STATE_SEED], bump)]
     pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ConversionWindow::INIT_SPACE,
+        seeds = [CONVERSION_WINDOW_SEED],
+        bump
+    )]
+    pub global_conversion_window: Account<'info, ConversionWindow>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ConversionWindow::INIT_SPACE,
+        seeds = [CONVERSION_WINDOW_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_conversion_window: Account<'info, ConversionWindow>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
+    #[account(mut, token::mint = governance_token_mint)]
+    pub treasury_governance_token_account: Option<Account<'info, TokenAccount>>,
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct GlobalState {
     pub admin: Pubkey,
     pub governance_token_mint: Pubkey,
     pub st_governance_token_mint: Pubkey,
+    pub vault_count: u64,
+    pub paused: bool,
+    pub timelock_delay: i64,
+    pub queued_action_count: u64,
+    pub proposal_count: u64,
+    pub proposal_config: ProposalConfig,
+    pub total_staked: u64,
+    pub reward_per_token_stored: u128,
+    pub reward_rate: u64,
+    pub reward_period_end: i64,
+    pub rewards_last_updated: i64,
+    pub conversion_config: ConversionConfig,
+    pub conversion_limits: ConversionLimits,
+    /// Layout version. Fields added later go at the end, so older accounts
+    /// decode as a prefix once `migrate_global_state` reallocs them.
+    pub version: u8,
+    /// Zeroed space for future fields.
+    pub reserved: [u8; 128],
+}
+
+impl GlobalState {
+    /// Accrues staking rewards into `reward_per_token_stored` up to `now`.
+    fn update_reward_per_token(&mut self, now: i64) -> Result<()> {
+        let applicable = now.min(self.reward_period_end);
+        if applicable <= self.rewards_last_updated {
+            return Ok(());
+        }
+        if self.total_staked > 0 {
+            let elapsed = (applicable - self.rewards_last_updated) as u128;
+            let accrued = elapsed
+                .checked_mul(self.reward_rate as u128)
+                .and_then(|rewards| rewards.checked_mul(REWARD_PRECISION))
+                .ok_or(ErrorCode::RewardOverflow)?
+                / self.total_staked as u128;
+            self.reward_per_token_stored = self
+                .reward_per_token_stored
+                .checked_add(accrued)
+                .ok_or(ErrorCode::RewardOverflow)?;
+        }
+        self.rewards_last_updated = applicable;
+        Ok(())
+    }
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct Vault {
     pub owner: Pubkey,
     pub governance_token_mint: Pubkey,
@@ -584,13 +2328,332 @@ pub struct Vault {
     pub max_vote_cap: u128,
     pub deadline: i64,
     pub st_governance_token_mint: Pubkey,
+    pub max_per_user: Option<u128>,
+    pub min_per_vote: Option<u64>,
+    pub merkle_root: Option<[u8; 32]>,
+    pub voting_start: i64,
+    pub max_deadline_extension: i64,
+    pub deadline_extended: i64,
+    pub mode: VaultMode,
+    pub total_locked: u128,
+    /// Lock weight cast with `ve_vote`, tallied separately. It counts
+    /// towards neither the vote cap nor `total_burned`, which prices claims.
+    pub total_ve_weight: u128,
+    /// Layout version. Fields added later go at the end, so older accounts
+    /// decode as a prefix once `migrate_vault` reallocs them.
+    pub version: u8,
+    /// Zeroed space for future fields.
+    pub reserved: [u8; 64],
+}
+
+impl Vault {
+    /// Validates a vote of `amount` against the voting window, caps and
+    /// allowlist, returning the new vault and user totals.
+    fn check_vote(
+        &self,
+        vault_total: u128,
+        user_total: u128,
+        user: &Pubkey,
+        amount: u64,
+        proof: &[[u8; 32]],
+        allocation: Option<u64>,
+    ) -> Result<(u128, u128)> {
+        self.check_voting_open()?;
+
+        // Check if the new total exceeds the max vote cap
+        let new_vault_total = vault_total
+            .checked_add(amount as u128)
+            .ok_or(ErrorCode::VoteOverflow)?;
+        require!(
+            new_vault_total <= self.max_vote_cap,
+            ErrorCode::MaxVoteCapExceeded
+        );
+
+        // Check per-user limits
+        if let Some(min_per_vote) = self.min_per_vote {
+            require!(amount >= min_per_vote, ErrorCode::BelowMinPerVote);
+        }
+        let new_user_total = user_total
+            .checked_add(amount as u128)
+            .ok_or(ErrorCode::VoteOverflow)?;
+        if let Some(max_per_user) = self.max_per_user {
+            require!(
+                new_user_total <= max_per_user,
+                ErrorCode::MaxPerUserExceeded
+            );
+        }
+
+        self.check_allowlisted(user, proof, allocation)?;
+        if let (Some(_), Some(allocation)) = (self.merkle_root, allocation) {
+            require!(
+                new_user_total <= allocation as u128,
+                ErrorCode::AllocationExceeded
+            );
+        }
+
+        Ok((new_vault_total, new_user_total))
+    }
+
+    fn check_voting_open(&self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.voting_start, ErrorCode::VotingNotStarted);
+        require!(now < self.deadline, ErrorCode::VotingEnded);
+        Ok(())
+    }
+
+    /// Checks `user` against the allowlist, if the vault has one.
+    fn check_allowlisted(
+        &self,
+        user: &Pubkey,
+        proof: &[[u8; 32]],
+        allocation: Option<u64>,
+    ) -> Result<()> {
+        if let Some(merkle_root) = self.merkle_root {
+            let leaf = match allocation {
+                Some(allocation) => hashv(&[user.as_ref(), &allocation.to_le_bytes()]),
+                None => hashv(&[user.as_ref()]),
+            };
+            require!(
+                verify_merkle_proof(proof, merkle_root, leaf.to_bytes()),
+                ErrorCode::NotAllowlisted
+            );
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Role {
+    pub holder: Pubkey,
+    pub kind: RoleKind,
+    pub granted_by: Pubkey,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct QueuedAction {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub eta: i64,
+    pub action: GovernanceAction,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub voting_end: i64,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub abstain_votes: u64,
+    pub quorum: u64,
+    pub threshold_bps: u16,
+    pub executed: bool,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProposalVote {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub choice: VoteChoice,
+    pub amount: u64,
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct UserVault {
     pub user: Pubkey,
     pub vault: Pubkey,
     pub burned_amount: u128,
+    pub locked_amount: u128,
+    pub ve_voted: bool,
+    /// Lock weight cast with `ve_vote`, which pays out no claim and isn't
+    /// limited by `max_per_user` or the allowlist allocation.
+    pub ve_weight: u128,
+    /// Layout version. Fields added later go at the end, so older accounts
+    /// decode as a prefix once `migrate_user_vault` reallocs them.
+    pub version: u8,
+    /// Zeroed space for future fields.
+    pub reserved: [u8; 32],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakePosition {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reward_per_token_paid: u128,
+    pub rewards_owed: u64,
+}
+
+impl StakePosition {
+    /// Moves rewards earned since the last checkpoint into `rewards_owed`.
+    fn checkpoint(&mut self, reward_per_token: u128) -> Result<()> {
+        let earned = (self.amount as u128)
+            .checked_mul(reward_per_token - self.reward_per_token_paid)
+            .ok_or(ErrorCode::RewardOverflow)?
+            / REWARD_PRECISION;
+        self.rewards_owed = u64::try_from(earned)
+            .ok()
+            .and_then(|earned| self.rewards_owed.checked_add(earned))
+            .ok_or(ErrorCode::RewardOverflow)?;
+        self.reward_per_token_paid = reward_per_token;
+        Ok(())
+    }
+}
+
+/// Governance converted recently, either DAO-wide or for a single user.
+///
+/// The window is kept as a ring of `CONVERSION_WINDOW_SLOTS` buckets, each
+/// `slot_length` seconds long. The ring always covers at least one full
+/// window, so no span of `window` seconds can convert more than the cap.
+#[account]
+#[derive(InitSpace)]
+pub struct ConversionWindow {
+    /// Bucket length the ring was filled with; a new length clears the ring.
+    pub slot_length: i64,
+    /// Index (`unix_timestamp / slot_length`) of the newest bucket.
+    pub slot: i64,
+    /// Converted per bucket, indexed by slot modulo the ring size.
+    pub converted: [u64; CONVERSION_WINDOW_SLOTS],
+}
+
+impl ConversionWindow {
+    /// Total converted across the buckets still in the window.
+    pub fn total(&self) -> u64 {
+        self.converted
+            .iter()
+            .fold(0, |total, amount| total.saturating_add(*amount))
+    }
+
+    /// Adds `amount` at time `now` if the rolling `window` stays within
+    /// `cap`. A `cap` of zero means unlimited.
+    fn record(&mut self, now: i64, window: i64, amount: u64, cap: u64) -> Result<()> {
+        require!(window > 0, ErrorCode::InvalidConversionLimits);
+        // One bucket fewer than the ring covers the whole window, so the
+        // ring sum includes every conversion of the last `window` seconds
+        let buckets = CONVERSION_WINDOW_SLOTS as i64 - 1;
+        let slot_length = (window + buckets - 1) / buckets;
+        let slot = now / slot_length;
+        if self.slot_length != slot_length {
+            self.slot_length = slot_length;
+            self.slot = slot;
+            self.converted = [0; CONVERSION_WINDOW_SLOTS];
+        }
+        // Clear the buckets that rolled out since the last conversion
+        let expired = (slot - self.slot).clamp(0, CONVERSION_WINDOW_SLOTS as i64);
+        for offset in 1..=expired {
+            let index = (self.slot + offset).rem_euclid(CONVERSION_WINDOW_SLOTS as i64);
+            self.converted[index as usize] = 0;
+        }
+        self.slot = self.slot.max(slot);
+
+        let converted = self
+            .total()
+            .checked_add(amount)
+            .ok_or(ErrorCode::ConversionCapExceeded)?;
+        require!(
+            cap == 0 || converted <= cap,
+            ErrorCode::ConversionCapExceeded
+        );
+        let index = slot.rem_euclid(CONVERSION_WINDOW_SLOTS as i64) as usize;
+        self.converted[index] = self.converted[index]
+            .checked_add(amount)
+            .ok_or(ErrorCode::ConversionCapExceeded)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VeLock {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub lock_start: i64,
+    pub unlock_time: i64,
+    /// Weight already cast with `ve_vote`, in any vault.
+    pub weight_used: u64,
+}
+
+impl VeLock {
+    /// Voting weight at `now`, decaying linearly from `amount` for a
+    /// `MAX_VE_LOCK_DURATION` lock down to zero at `unlock_time`.
+    pub fn voting_weight(&self, now: i64) -> u64 {
+        let remaining = self.unlock_time.saturating_sub(now).max(0) as u128;
+        (self.amount as u128 * remaining / MAX_VE_LOCK_DURATION as u128) as u64
+    }
+}
+
+#[event]
+pub struct VeVoteCast {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub weight: u64,
+}
+
+#[event]
+pub struct VaultScheduleUpdated {
+    pub vault: Pubkey,
+    pub old_deadline: i64,
+    pub new_deadline: i64,
+    pub convert_time: i64,
+}
+
+#[event]
+pub struct MaxVoteCapUpdated {
+    pub vault: Pubkey,
+    pub old_max_vote_cap: u128,
+    pub new_max_vote_cap: u128,
+}
+
+#[event]
+pub struct ActionQueued {
+    pub id: u64,
+    pub action: GovernanceAction,
+    pub eta: i64,
+}
+
+#[event]
+pub struct ActionExecuted {
+    pub id: u64,
+}
+
+#[event]
+pub struct ActionCancelled {
+    pub id: u64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub voting_end: i64,
+}
+
+#[event]
+pub struct ProposalVoteCast {
+    pub id: u64,
+    pub voter: Pubkey,
+    pub choice: VoteChoice,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub id: u64,
+    pub queued_action_id: u64,
+}
+
+#[event]
+pub struct RewardsFunded {
+    pub amount: u64,
+    pub duration: i64,
+    pub reward_rate: u64,
 }
 
 #[error_code]
@@ -607,6 +2670,92 @@ pub enum ErrorCode {
     VotingEnded,
     #[msg("Max vote cap exceeded")]
     MaxVoteCapExceeded,
+    #[msg("Max per-user vote cap exceeded")]
+    MaxPerUserExceeded,
+    #[msg("Vote amount is below the vault minimum")]
+    BelowMinPerVote,
+    #[msg("User is not on the vault allowlist")]
+    NotAllowlisted,
+    #[msg("Allowlist allocation exceeded")]
+    AllocationExceeded,
+    #[msg("Voting has already started")]
+    VotingAlreadyStarted,
+    #[msg("Voting has not started yet")]
+    VotingNotStarted,
+    #[msg("Voting start must be before the deadline")]
+    InvalidVotingWindow,
+    #[msg("Deadline extension exceeds the vault maximum")]
+    MaxExtensionExceeded,
+    #[msg("Invalid schedule update")]
+    InvalidScheduleUpdate,
+    #[msg("Max vote cap cannot be below the votes already cast")]
+    MaxVoteCapBelowBurned,
+    #[msg("Vault count overflow")]
+    VaultCountOverflow,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Program is paused")]
+    Paused,
+    #[msg("Invalid timelock delay")]
+    InvalidTimelockDelay,
+    #[msg("Queued action count overflow")]
+    QueuedActionCountOverflow,
+    #[msg("Timelock has not expired")]
+    TimelockNotExpired,
+    #[msg("Invalid proposal config")]
+    InvalidProposalConfig,
+    #[msg("Proposal count overflow")]
+    ProposalCountOverflow,
+    #[msg("Proposal voting has ended")]
+    ProposalVotingEnded,
+    #[msg("Proposal voting is still active")]
+    ProposalVotingActive,
+    #[msg("Cannot change vote choice")]
+    VoteChoiceMismatch,
+    #[msg("Proposal quorum not reached")]
+    QuorumNotReached,
+    #[msg("Proposal did not pass")]
+    ProposalRejected,
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Instruction not supported for this vault mode")]
+    WrongVaultMode,
+    #[msg("Voting period has not ended")]
+    VotingNotEnded,
+    #[msg("Invalid lock amount or duration")]
+    InvalidVeLock,
+    #[msg("Lock has not expired")]
+    VeLockNotExpired,
+    #[msg("Lock weight already used in this vault")]
+    VeWeightAlreadyUsed,
+    #[msg("No voting weight")]
+    NoVotingWeight,
+    #[msg("Insufficient staked balance")]
+    InsufficientStake,
+    #[msg("Invalid reward amount or duration")]
+    InvalidRewardFunding,
+    #[msg("Reward overflow")]
+    RewardOverflow,
+    #[msg("Invalid conversion config")]
+    InvalidConversionConfig,
+    #[msg("Conversion amount too small")]
+    ConversionTooSmall,
+    #[msg("Conversion overflow")]
+    ConversionOverflow,
+    #[msg("Invalid treasury account")]
+    InvalidTreasuryAccount,
+    #[msg("Invalid conversion limits")]
+    InvalidConversionLimits,
+    #[msg("Conversion cap exceeded for the current window")]
+    ConversionCapExceeded,
+    #[msg("Account is not a migratable program account")]
+    InvalidMigration,
+    #[msg("Account is already on the current layout version")]
+    AlreadyMigrated,
+    #[msg("Claims cannot open before voting ends")]
+    ConvertTimeBeforeDeadline,
+    #[msg("Queued action can't be executed by this instruction")]
+    WrongAction,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
@@ -615,3 +2764,275 @@ pub struct TokenMetadata {
     pub symbol: String,
     pub uri: String,
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleKind {
+    Admin,
+    Operator,
+    Pauser,
+    Treasurer,
+    ProjectPartner,
+}
+
+/// A change to `GlobalState` parameters or roles, applied through the
+/// timelock.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug, Clone, PartialEq, Eq)]
+pub enum GovernanceAction {
+    SetPaused { paused: bool },
+    TransferAdmin { new_admin: Pubkey },
+    SetTimelockDelay { delay: i64 },
+    SetProposalConfig { config: ProposalConfig },
+    SetConversionConfig { config: ConversionConfig },
+    SetConversionLimits { limits: ConversionLimits },
+    /// Executed by `grant_role`, which creates the role account.
+    GrantRole { kind: RoleKind, holder: Pubkey },
+    /// Executed by `revoke_role`, which closes the role account.
+    RevokeRole { kind: RoleKind, holder: Pubkey },
+}
+
+impl GovernanceAction {
+    fn validate(&self) -> Result<()> {
+        match self {
+            GovernanceAction::SetTimelockDelay { delay } => {
+                require!(*delay >= 0, ErrorCode::InvalidTimelockDelay);
+            }
+            GovernanceAction::SetProposalConfig { config } => config.validate()?,
+            GovernanceAction::SetConversionConfig { config } => config.validate()?,
+            GovernanceAction::SetConversionLimits { limits } => {
+                require!(limits.window > 0, ErrorCode::InvalidConversionLimits);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn apply(&self, global_state: &mut GlobalState) -> Result<()> {
+        self.validate()?;
+        match *self {
+            GovernanceAction::SetPaused { paused } => global_state.paused = paused,
+            GovernanceAction::TransferAdmin { new_admin } => global_state.admin = new_admin,
+            GovernanceAction::SetTimelockDelay { delay } => global_state.timelock_delay = delay,
+            GovernanceAction::SetProposalConfig { config } => global_state.proposal_config = config,
+            GovernanceAction::SetConversionConfig { config } => {
+                global_state.conversion_config = config
+            }
+            GovernanceAction::SetConversionLimits { limits } => {
+                global_state.conversion_limits = limits
+            }
+            GovernanceAction::GrantRole { .. } | GovernanceAction::RevokeRole { .. } => {
+                return err!(ErrorCode::WrongAction)
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProposalConfig {
+    pub voting_period: i64,
+    pub quorum: u64,
+    pub threshold_bps: u16,
+}
+
+impl ProposalConfig {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.voting_period > 0 && self.threshold_bps <= 10_000,
+            ErrorCode::InvalidProposalConfig
+        );
+        Ok(())
+    }
+}
+
+/// Terms for converting governance tokens into st_governance tokens.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversionConfig {
+    pub rate_numerator: u64,
+    pub rate_denominator: u64,
+    pub fee_bps: u16,
+    /// Owner of the governance token account that receives conversion fees.
+    pub treasury: Pubkey,
+}
+
+impl ConversionConfig {
+    /// Converts one for one without a fee.
+    pub fn one_to_one(treasury: Pubkey) -> Self {
+        Self {
+            rate_numerator: 1,
+            rate_denominator: 1,
+            fee_bps: 0,
+            treasury,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.rate_numerator > 0 && self.rate_denominator > 0 && self.fee_bps <= 10_000,
+            ErrorCode::InvalidConversionConfig
+        );
+        Ok(())
+    }
+
+    /// Splits `amount` into `(fee, burned, minted)`. Both the fee and the
+    /// minted amount round down.
+    pub fn convert(&self, amount: u64) -> Result<(u64, u64, u64)> {
+        self.validate()?;
+        let fee = (amount as u128 * self.fee_bps as u128 / 10_000) as u64;
+        let net_amount = amount - fee;
+        let st_amount = u64::try_from(
+            net_amount as u128 * self.rate_numerator as u128 / self.rate_denominator as u128,
+        )
+        .map_err(|_| ErrorCode::ConversionOverflow)?;
+        require!(st_amount > 0, ErrorCode::ConversionTooSmall);
+        Ok((fee, net_amount, st_amount))
+    }
+}
+
+/// Conversion caps over a rolling window of `window` seconds. A cap of zero
+/// disables that limit.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversionLimits {
+    pub window: i64,
+    pub global_cap: u64,
+    pub per_user_cap: u64,
+}
+
+impl ConversionLimits {
+    /// A one-day window without caps.
+    pub const UNLIMITED: Self = Self {
+        window: 24 * 60 * 60,
+        global_cap: 0,
+        per_user_cap: 0,
+    };
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteChoice {
+    Yes,
+    No,
+    Abstain,
+}
+
+/// How a vault turns st_governance into voting weight.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultMode {
+    /// st_governance is burned for vote tokens that claim project tokens.
+    Burn,
+    /// st_governance is escrowed until the deadline, then returned.
+    Lock,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleUpdate {
+    Extend { seconds: i64 },
+    CloseNow,
+}
+
+/// Checks that `authority` is the global admin or holds `kind` through `role`.
+/// The admin role stands in for every other kind.
+fn require_role(
+    global_state: &GlobalState,
+    authority: &Pubkey,
+    role: Option<&Account<Role>>,
+    kind: RoleKind,
+) -> Result<()> {
+    if *authority == global_state.admin {
+        return Ok(());
+    }
+    let role = role.ok_or(ErrorCode::Unauthorized)?;
+    require!(
+        role.holder == *authority && (role.kind == kind || role.kind == RoleKind::Admin),
+        ErrorCode::Unauthorized
+    );
+    Ok(())
+}
+
+/// Stores `action` as the next queued action, applicable by `execute_action`
+/// once the timelock delay has passed.
+fn enqueue_action(
+    global_state: &mut GlobalState,
+    queued_action: &mut QueuedAction,
+    proposer: Pubkey,
+    action: GovernanceAction,
+) -> Result<()> {
+    let id = global_state.queued_action_count;
+    global_state.queued_action_count = id
+        .checked_add(1)
+        .ok_or(ErrorCode::QueuedActionCountOverflow)?;
+    let eta = Clock::get()?
+        .unix_timestamp
+        .checked_add(global_state.timelock_delay)
+        .ok_or(ErrorCode::InvalidTimelockDelay)?;
+
+    queued_action.id = id;
+    queued_action.proposer = proposer;
+    queued_action.eta = eta;
+    queued_action.action = action.clone();
+
+    emit!(ActionQueued { id, action, eta });
+
+    Ok(())
+}
+
+/// Claims are priced from the vault's final vote total, so they may not
+/// open at `convert_time` while votes can still land before `deadline`.
+fn require_claims_after_voting(convert_time: i64, deadline: i64) -> Result<()> {
+    require!(
+        convert_time >= deadline,
+        ErrorCode::ConvertTimeBeforeDeadline
+    );
+    Ok(())
+}
+
+/// Grows `account`, written under an older layout of `T`, to the current
+/// size of `T` with `payer` topping up rent, then applies `upgrade`. The
+/// appended bytes are zeroed, so fields added since decode as zero.
+fn migrate_account<'info, T>(
+    account: &UncheckedAccount<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    upgrade: impl FnOnce(&mut T) -> Result<()>,
+) -> Result<()>
+where
+    T: AccountSerialize + AccountDeserialize + Space,
+{
+    let account = account.to_account_info();
+    require_keys_eq!(*account.owner, crate::ID, ErrorCode::InvalidMigration);
+
+    let space = 8 + T::INIT_SPACE;
+    if account.data_len() < space {
+        let rent = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(account.lamports());
+        if rent > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                rent,
+            )?;
+        }
+        account.realloc(space, true)?;
+    }
+
+    let mut value = T::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    upgrade(&mut value)?;
+    value.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Verifies a merkle proof built with sorted-pair sha256 hashing.
+fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == root
+}
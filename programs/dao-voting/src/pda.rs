@@ -0,0 +1,84 @@
+//! PDA seeds and address derivation shared by the program's account
+//! constraints and off-chain clients.
+
+use anchor_lang::prelude::*;
+
+use crate::RoleKind;
+
+pub const GLOBAL_STATE_SEED: &[u8] = b"global_state";
+pub const GOVERNANCE_TOKEN_MINT_SEED: &[u8] = b"governance_token_mint";
+pub const ST_GOVERNANCE_TOKEN_MINT_SEED: &[u8] = b"st_governance_token_mint";
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const VOTE_TOKEN_MINT_SEED: &[u8] = b"vote_token_mint";
+pub const USER_VAULT_SEED: &[u8] = b"user_vault";
+pub const ROLE_SEED: &[u8] = b"role";
+pub const QUEUED_ACTION_SEED: &[u8] = b"queued_action";
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+pub const PROPOSAL_VOTE_SEED: &[u8] = b"proposal_vote";
+pub const STAKE_SEED: &[u8] = b"stake";
+pub const VE_LOCK_SEED: &[u8] = b"ve_lock";
+pub const CONVERSION_WINDOW_SEED: &[u8] = b"conversion_window";
+
+pub fn find_global_state_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_STATE_SEED], &crate::ID)
+}
+
+pub fn find_governance_token_mint_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GOVERNANCE_TOKEN_MINT_SEED], &crate::ID)
+}
+
+pub fn find_st_governance_token_mint_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ST_GOVERNANCE_TOKEN_MINT_SEED], &crate::ID)
+}
+
+pub fn find_vault_address(vault_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, &vault_id.to_le_bytes()], &crate::ID)
+}
+
+pub fn find_vote_token_mint_address(vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VOTE_TOKEN_MINT_SEED, vault.as_ref()], &crate::ID)
+}
+
+pub fn find_user_vault_address(vault: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[USER_VAULT_SEED, vault.as_ref(), user.as_ref()],
+        &crate::ID,
+    )
+}
+
+pub fn find_role_address(kind: RoleKind, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ROLE_SEED, &[kind as u8], holder.as_ref()], &crate::ID)
+}
+
+pub fn find_queued_action_address(id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[QUEUED_ACTION_SEED, &id.to_le_bytes()], &crate::ID)
+}
+
+pub fn find_proposal_address(id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROPOSAL_SEED, &id.to_le_bytes()], &crate::ID)
+}
+
+pub fn find_proposal_vote_address(proposal: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROPOSAL_VOTE_SEED, proposal.as_ref(), voter.as_ref()],
+        &crate::ID,
+    )
+}
+
+pub fn find_stake_position_address(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_SEED, owner.as_ref()], &crate::ID)
+}
+
+pub fn find_ve_lock_address(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VE_LOCK_SEED, owner.as_ref()], &crate::ID)
+}
+
+/// DAO-wide conversions within the rolling conversion window.
+pub fn find_global_conversion_window_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONVERSION_WINDOW_SEED], &crate::ID)
+}
+
+/// A single user's conversions within the rolling conversion window.
+pub fn find_user_conversion_window_address(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONVERSION_WINDOW_SEED, user.as_ref()], &crate::ID)
+}
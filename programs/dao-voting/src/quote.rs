@@ -0,0 +1,279 @@
+//! Claim quotes and vault analytics computed from `Vault` and `UserVault`
+//! data. `claim_project_tokens` pays out `quote_claim`, so off-chain quotes
+//! match the program exactly.
+
+use anchor_lang::prelude::*;
+
+use crate::{UserVault, Vault, VaultMode};
+
+/// What `claim_project_tokens` would do for a user.
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClaimQuote {
+    /// Project tokens transferred to the user.
+    pub claim_amount: u64,
+    /// Vote tokens burned from the user.
+    pub vote_tokens_burned: u64,
+    /// Earliest timestamp the claim succeeds at.
+    pub claimable_at: i64,
+}
+
+/// Project tokens paid for `burned_amount` out of `total_burned` vote
+/// weight when the vault holds `total_deposited`, rounded down. Zero while
+/// nothing has been burned.
+pub fn claim_amount(total_deposited: u128, total_burned: u128, burned_amount: u128) -> u64 {
+    if total_burned == 0 {
+        return 0;
+    }
+    let amount = match total_deposited.checked_mul(burned_amount) {
+        Some(product) => product / total_burned,
+        // Only reachable with totals past the u64 token supply; divide first
+        // and accept the extra rounding
+        None => (total_deposited / total_burned).saturating_mul(burned_amount),
+    };
+    amount.min(u64::MAX as u128) as u64
+}
+
+pub fn quote_claim(vault: &Vault, user_vault: &UserVault) -> ClaimQuote {
+    ClaimQuote {
+        claim_amount: claim_amount(
+            vault.total_deposited,
+            vault.total_burned,
+            user_vault.burned_amount,
+        ),
+        vote_tokens_burned: user_vault.burned_amount.min(u64::MAX as u128) as u64,
+        claimable_at: vault.convert_time,
+    }
+}
+
+/// Vault and user totals after a vote that passes every vault check.
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoteQuote {
+    pub new_vault_total: u128,
+    pub new_user_total: u128,
+    pub remaining_vote_capacity: u128,
+}
+
+/// Point-in-time overview of a vault.
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VaultSummary {
+    pub vault_id: u64,
+    pub mode: VaultMode,
+    pub project_token_mint: Pubkey,
+    pub voting_start: i64,
+    pub deadline: i64,
+    pub convert_time: i64,
+    pub max_vote_cap: u128,
+    pub vote_total: u128,
+    pub remaining_vote_capacity: u128,
+    pub total_deposited: u128,
+    pub voting_open: bool,
+    pub claimable: bool,
+}
+
+pub fn vault_summary(vault: &Vault, now: i64) -> VaultSummary {
+    VaultSummary {
+        vault_id: vault.vault_id,
+        mode: vault.mode,
+        project_token_mint: vault.project_token_mint,
+        voting_start: vault.voting_start,
+        deadline: vault.deadline,
+        convert_time: vault.convert_time,
+        max_vote_cap: vault.max_vote_cap,
+        vote_total: vault_vote_total(vault),
+        remaining_vote_capacity: remaining_vote_capacity(vault),
+        total_deposited: vault.total_deposited,
+        voting_open: now >= vault.voting_start && now < vault.deadline,
+        claimable: vault.project_token_mint != Pubkey::default() && now >= vault.convert_time,
+    }
+}
+
+/// Vote weight counted against `max_vote_cap`. Lock weight cast with
+/// `ve_vote` is tallied separately in `total_ve_weight`.
+pub fn vault_vote_total(vault: &Vault) -> u128 {
+    match vault.mode {
+        VaultMode::Burn => vault.total_burned,
+        VaultMode::Lock => vault.total_locked,
+    }
+}
+
+/// The user's vote weight in `vault`, excluding lock weight.
+pub fn user_vote_total(vault: &Vault, user_vault: &UserVault) -> u128 {
+    match vault.mode {
+        VaultMode::Burn => user_vault.burned_amount,
+        VaultMode::Lock => user_vault.locked_amount,
+    }
+}
+
+/// The user's share of the vault's vote weight in basis points, rounded
+/// down. Zero while the vault has no votes.
+pub fn share_bps(vault: &Vault, user_vault: &UserVault) -> u16 {
+    let total = vault_vote_total(vault);
+    if total == 0 {
+        return 0;
+    }
+    let user_total = user_vote_total(vault, user_vault).min(total);
+    // user_total <= total, so the quotient is at most 10_000
+    (user_total.saturating_mul(10_000) / total) as u16
+}
+
+/// Votes the vault still accepts before reaching `max_vote_cap`.
+pub fn remaining_vote_capacity(vault: &Vault) -> u128 {
+    vault.max_vote_cap.saturating_sub(vault_vote_total(vault))
+}
+
+/// Votes the user can still cast, bounded by both the vault cap and
+/// `max_per_user`.
+pub fn remaining_user_capacity(vault: &Vault, user_vault: &UserVault) -> u128 {
+    let remaining = remaining_vote_capacity(vault);
+    match vault.max_per_user {
+        Some(max_per_user) => {
+            remaining.min(max_per_user.saturating_sub(user_vote_total(vault, user_vault)))
+        }
+        None => remaining,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(mode: VaultMode, max_vote_cap: u128) -> Vault {
+        Vault {
+            owner: Pubkey::default(),
+            governance_token_mint: Pubkey::default(),
+            vote_token_mint: Pubkey::default(),
+            project_token_mint: Pubkey::default(),
+            convert_time: 0,
+            total_burned: 0,
+            total_deposited: 0,
+            vault_id: 0,
+            max_vote_cap,
+            deadline: 0,
+            st_governance_token_mint: Pubkey::default(),
+            max_per_user: None,
+            min_per_vote: None,
+            merkle_root: None,
+            voting_start: 0,
+            max_deadline_extension: 0,
+            deadline_extended: 0,
+            mode,
+            total_locked: 0,
+            total_ve_weight: 0,
+            version: crate::VAULT_VERSION,
+            reserved: [0; 64],
+        }
+    }
+
+    fn user_vault(burned_amount: u128) -> UserVault {
+        UserVault {
+            user: Pubkey::default(),
+            vault: Pubkey::default(),
+            burned_amount,
+            locked_amount: 0,
+            ve_voted: false,
+            ve_weight: 0,
+            version: crate::USER_VAULT_VERSION,
+            reserved: [0; 32],
+        }
+    }
+
+    #[test]
+    fn claims_nothing_without_burns_or_deposits() {
+        assert_eq!(claim_amount(0, 0, 0), 0);
+        assert_eq!(claim_amount(1_000, 0, 0), 0);
+        assert_eq!(claim_amount(0, 500, 500), 0);
+
+        let vault = vault(VaultMode::Burn, 100);
+        let user_vault = user_vault(0);
+        assert_eq!(quote_claim(&vault, &user_vault).claim_amount, 0);
+        assert_eq!(share_bps(&vault, &user_vault), 0);
+        assert_eq!(remaining_vote_capacity(&vault), 100);
+    }
+
+    #[test]
+    fn rounds_claims_down() {
+        assert_eq!(claim_amount(1_000, 3, 1), 333);
+        assert_eq!(claim_amount(1_000, 3, 2), 666);
+        assert_eq!(claim_amount(1_000, 3, 3), 1_000);
+        assert_eq!(claim_amount(1, 3, 2), 0);
+        // Shares never pay out more than the vault holds in total
+        let paid: u64 = [1, 1, 1]
+            .iter()
+            .map(|burned| claim_amount(100, 3, *burned))
+            .sum();
+        assert!(paid <= 100);
+    }
+
+    /// The f64 formula claims paid out before the switch to integer math.
+    fn f64_claim_amount(total_deposited: u128, total_burned: u128, burned_amount: u128) -> u64 {
+        let user_ratio = (burned_amount as f64) / (total_burned as f64);
+        ((total_deposited as f64 * user_ratio) as u128).min(u64::MAX as u128) as u64
+    }
+
+    #[test]
+    fn pays_the_f64_amount_or_one_more_token_below_it() {
+        for total_deposited in 0..200u128 {
+            for total_burned in 1..60u128 {
+                for burned_amount in 0..=total_burned {
+                    let old = f64_claim_amount(total_deposited, total_burned, burned_amount);
+                    let new = claim_amount(total_deposited, total_burned, burned_amount);
+                    assert!(new == old || new == old + 1);
+                    assert_eq!(new as u128, total_deposited * burned_amount / total_burned);
+                }
+            }
+        }
+        // f64 rounded 15/22 of 22 tokens down to 14
+        assert_eq!(f64_claim_amount(22, 22, 15), 14);
+        assert_eq!(claim_amount(22, 22, 15), 15);
+        // and rounded shares near the u64 supply up to the whole deposit
+        let total = u64::MAX as u128;
+        assert_eq!(f64_claim_amount(total, total, total - 1), u64::MAX);
+        assert_eq!(claim_amount(total, total, total - 1), u64::MAX - 1);
+    }
+
+    #[test]
+    fn caps_claims_at_u64() {
+        assert_eq!(claim_amount(u128::MAX, 1, 1), u64::MAX);
+        assert_eq!(claim_amount(u128::MAX, u128::MAX, u128::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn reports_no_capacity_once_the_cap_is_reached() {
+        let mut vault = vault(VaultMode::Burn, 100);
+        vault.total_burned = 100;
+        // Lock weight is tallied separately and leaves capacity untouched
+        vault.total_ve_weight = 40;
+        let mut user_vault = user_vault(60);
+        assert_eq!(vault_vote_total(&vault), 100);
+        assert_eq!(remaining_vote_capacity(&vault), 0);
+        assert_eq!(remaining_user_capacity(&vault, &user_vault), 0);
+
+        // Lowering the cap below the total does not underflow
+        vault.max_vote_cap = 50;
+        assert_eq!(remaining_vote_capacity(&vault), 0);
+
+        vault.max_vote_cap = 200;
+        vault.max_per_user = Some(70);
+        assert_eq!(remaining_user_capacity(&vault, &user_vault), 10);
+        user_vault.ve_weight = 20;
+        assert_eq!(remaining_user_capacity(&vault, &user_vault), 10);
+    }
+
+    #[test]
+    fn computes_share_bps_from_zero_to_all() {
+        let mut vault = vault(VaultMode::Lock, 1_000);
+        vault.total_locked = 300;
+        let mut user_vault = user_vault(0);
+        assert_eq!(share_bps(&vault, &user_vault), 0);
+
+        user_vault.locked_amount = 1;
+        assert_eq!(share_bps(&vault, &user_vault), 33);
+
+        user_vault.locked_amount = 300;
+        assert_eq!(share_bps(&vault, &user_vault), 10_000);
+
+        // Never above 100% even if the user total exceeds the vault's
+        user_vault.locked_amount = 400;
+        assert_eq!(share_bps(&vault, &user_vault), 10_000);
+    }
+}
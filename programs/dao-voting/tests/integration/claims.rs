@@ -0,0 +1,121 @@
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use dao_voting::quote::ClaimQuote;
+use dao_voting::{ErrorCode, RoleKind, UserVault, Vault};
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+#[tokio::test]
+async fn deposits_and_claims_project_tokens() {
+    let mut env = TestEnv::new().await;
+    let partner = env.new_user().await;
+    let large = env.new_user().await;
+    let small = env.new_user().await;
+    let params = VaultParams::new(env.now);
+    let convert_time = params.deadline + DAY;
+    let vault_id = env.create_vault(params).await;
+    env.fund_st_governance(&large, 300).await;
+    env.fund_st_governance(&small, 100).await;
+    env.vote(&large, vault_id, 300).await.unwrap();
+    env.vote(&small, vault_id, 100).await.unwrap();
+
+    let project_mint = env.create_mint().await;
+    let partner_account = env.mint_to(&project_mint, &partner.pubkey(), 1_000).await;
+    let ix = set_project_token_ix(
+        &env.admin.pubkey(),
+        None,
+        vault_id,
+        project_mint,
+        convert_time,
+    );
+    env.send_admin(&[ix]).await.unwrap();
+
+    let ix = deposit_ix(&partner.pubkey(), None, vault_id, &project_mint, 1_000);
+    assert_error(env.send(&[ix], &[&partner]).await, ErrorCode::Unauthorized);
+
+    env.grant_role(RoleKind::ProjectPartner, &partner.pubkey())
+        .await;
+    let role = Some(role_address(RoleKind::ProjectPartner, &partner.pubkey()));
+    // Deposits of any other mint would inflate total_deposited
+    let other_mint = env.create_mint().await;
+    env.mint_to(&other_mint, &partner.pubkey(), 1_000).await;
+    let ix = deposit_ix(&partner.pubkey(), role, vault_id, &other_mint, 1_000);
+    assert_custom_error(
+        env.send(&[ix], &[&partner]).await,
+        AnchorErrorCode::ConstraintAddress.into(),
+    );
+    let ix = deposit_ix(&partner.pubkey(), role, vault_id, &project_mint, 1_000);
+    env.send(&[ix], &[&partner]).await.unwrap();
+    assert_eq!(env.token_balance(&partner_account).await, 0);
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.total_deposited, 1_000);
+    assert_eq!(vault.project_token_mint, project_mint);
+
+    let ix = claim_ix(&large.pubkey(), vault_id, &project_mint);
+    assert_error(
+        env.send(&[ix], &[&large]).await,
+        ErrorCode::ClaimNotAvailable,
+    );
+
+    let quote: ClaimQuote = env
+        .view(quote_claim_ix(&large.pubkey(), vault_id))
+        .await
+        .unwrap();
+    assert_eq!(
+        quote,
+        ClaimQuote {
+            claim_amount: 750,
+            vote_tokens_burned: 300,
+            claimable_at: convert_time,
+        }
+    );
+
+    env.warp_to(convert_time);
+    let ix = deposit_ix(&partner.pubkey(), role, vault_id, &project_mint, 0);
+    assert_error(
+        env.send(&[ix], &[&partner]).await,
+        ErrorCode::DepositNotAllowed,
+    );
+
+    for (user, expected) in [(&large, 750), (&small, 250)] {
+        let ix = claim_ix(&user.pubkey(), vault_id, &project_mint);
+        env.send(&[ix], &[user]).await.unwrap();
+        let project_account = ata(&user.pubkey(), &project_mint);
+        assert_eq!(env.token_balance(&project_account).await, expected);
+        let votes = ata(&user.pubkey(), &vote_mint(vault_id));
+        assert_eq!(env.token_balance(&votes).await, 0);
+        let user_vault: UserVault = env
+            .account(&user_vault_address(vault_id, &user.pubkey()))
+            .await;
+        assert_eq!(user_vault.burned_amount, 0);
+    }
+    assert_eq!(env.mint_supply(&vote_mint(vault_id)).await, 0);
+    let vault_account = ata(&vault_address(vault_id), &project_mint);
+    assert_eq!(env.token_balance(&vault_account).await, 0);
+}
+
+#[tokio::test]
+async fn rejects_claims_opening_before_the_deadline() {
+    let mut env = TestEnv::new().await;
+    let params = VaultParams::new(env.now);
+    let deadline = params.deadline;
+    let vault_id = env.create_vault(params).await;
+    let project_mint = env.create_mint().await;
+    let admin = env.admin.pubkey();
+
+    let ix = set_project_token_ix(&admin, None, vault_id, project_mint, deadline - 1);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::ConvertTimeBeforeDeadline,
+    );
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.project_token_mint, Default::default());
+
+    let ix = set_project_token_ix(&admin, None, vault_id, project_mint, deadline);
+    env.send_admin(&[ix]).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(
+        (vault.project_token_mint, vault.convert_time),
+        (project_mint, deadline)
+    );
+}
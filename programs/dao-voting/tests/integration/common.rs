@@ -0,0 +1,882 @@
+//! Program-test harness for the dao-voting program.
+//!
+//! The program runs natively while Metaplex runs from the bundled
+//! `metaplex_token_metadata_program.so`, so the suite needs neither a
+//! validator nor network access. The harness owns the clock: `warp_to` and
+//! `advance` move `unix_timestamp` for the next transaction.
+
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::{system_program, AccountDeserialize, AccountSerialize};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::spl_token;
+use dao_voting::{
+    pda, ConversionConfig, ConversionLimits, GovernanceAction, ProposalConfig, RoleKind,
+    TokenMetadata, VaultMode,
+};
+use dao_voting_client::{
+    CancelActionBuilder, CastProposalVoteBuilder, ClaimProjectTokensBuilder,
+    ClaimStakingRewardsBuilder, ConvertToStGovernanceBuilder, CreateProposalBuilder,
+    CreateStAAndVaultBuilder, CreateVeLockBuilder, DepositProjectTokensBuilder,
+    ExecuteActionBuilder, ExecuteProposalBuilder, FundRewardsBuilder, GrantRoleBuilder,
+    InitializeBuilder, LockVoteBuilder, MigrateGlobalStateBuilder, MigrateUserVaultBuilder,
+    MigrateVaultBuilder, PauseBuilder, QueueActionBuilder, QuoteClaimBuilder, QuoteVoteBuilder,
+    RevokeRoleBuilder, SetMerkleRootBuilder, SetProjectTokenBuilder, StakeStGovernanceBuilder,
+    UnlockVoteBuilder, UnstakeStGovernanceBuilder, UpdateMaxVoteCapBuilder,
+    UpdateVaultScheduleBuilder, UpdateVotingWindowBuilder, VaultSummaryBuilder, VeVoteBuilder,
+    VoteBuilder, WithdrawProposalVoteBuilder, WithdrawVeLockBuilder,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::sysvar::clock::Clock;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::{bpf_loader, system_instruction};
+
+/// Genesis time of every test, so schedules can use readable offsets.
+pub const START: i64 = 1_700_000_000;
+pub const DAY: i64 = 24 * 60 * 60;
+pub const TIMELOCK_DELAY: i64 = DAY;
+pub const VOTING_PERIOD: i64 = 3 * DAY;
+pub const QUORUM: u64 = 100;
+
+const METAPLEX_PROGRAM: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../tests/metaplex_token_metadata_program.so"
+));
+
+pub use dao_voting_client::metadata_address;
+
+const LAMPORTS: u64 = 100_000_000_000;
+
+// Anchor's entrypoint ties the account slice and its infos to one lifetime,
+// which the `processor!` signature can't express. Reborrow every account's
+// lamports and data for the length of the call instead, then carry any
+// realloc back to the runtime's infos, which share the same memory.
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // Duplicate accounts share their borrows, so reborrow each key once
+    let first = |key: &Pubkey| accounts.iter().position(|account| account.key == key);
+    let unique: Vec<usize> = (0..accounts.len())
+        .filter(|&index| first(accounts[index].key) == Some(index))
+        .collect();
+    let (result, data_lens) = {
+        let mut lamports: Vec<_> = unique
+            .iter()
+            .map(|&index| accounts[index].lamports.borrow_mut())
+            .collect();
+        let mut data: Vec<_> = unique
+            .iter()
+            .map(|&index| accounts[index].data.borrow_mut())
+            .collect();
+        let infos: Vec<AccountInfo> = unique
+            .iter()
+            .zip(lamports.iter_mut().zip(data.iter_mut()))
+            .map(|(&index, (lamports, data))| {
+                let account = &accounts[index];
+                AccountInfo::new(
+                    account.key,
+                    account.is_signer,
+                    account.is_writable,
+                    lamports,
+                    data,
+                    account.owner,
+                    account.executable,
+                    account.rent_epoch,
+                )
+            })
+            .collect();
+        let reborrowed: Vec<AccountInfo> = accounts
+            .iter()
+            .map(|account| {
+                let index = unique
+                    .iter()
+                    .position(|&index| accounts[index].key == account.key)
+                    .unwrap();
+                infos[index].clone()
+            })
+            .collect();
+
+        let result = dao_voting::entry(program_id, &reborrowed, instruction_data);
+        let data_lens: Vec<usize> = infos.iter().map(AccountInfo::data_len).collect();
+        (result, data_lens)
+    };
+    for (&index, data_len) in unique.iter().zip(data_lens) {
+        accounts[index].realloc(data_len, false)?;
+    }
+    result
+}
+
+pub fn token_metadata(name: &str) -> TokenMetadata {
+    TokenMetadata {
+        name: name.to_string(),
+        symbol: name.to_uppercase(),
+        uri: format!("https://example.com/{name}.json"),
+    }
+}
+
+pub fn global_state() -> Pubkey {
+    pda::find_global_state_address().0
+}
+
+pub fn governance_mint() -> Pubkey {
+    pda::find_governance_token_mint_address().0
+}
+
+pub fn st_governance_mint() -> Pubkey {
+    pda::find_st_governance_token_mint_address().0
+}
+
+pub fn vault_address(vault_id: u64) -> Pubkey {
+    pda::find_vault_address(vault_id).0
+}
+
+pub fn vote_mint(vault_id: u64) -> Pubkey {
+    pda::find_vote_token_mint_address(&vault_address(vault_id)).0
+}
+
+pub fn user_vault_address(vault_id: u64, user: &Pubkey) -> Pubkey {
+    pda::find_user_vault_address(&vault_address(vault_id), user).0
+}
+
+pub fn role_address(kind: RoleKind, holder: &Pubkey) -> Pubkey {
+    pda::find_role_address(kind, holder).0
+}
+
+pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, mint)
+}
+
+/// The transaction error a failed instruction with program error `code`
+/// produces.
+pub fn custom_error(code: dao_voting::ErrorCode) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.into()))
+}
+
+/// Asserts that `result` failed with the program error `code`.
+pub fn assert_error(result: Result<(), BanksClientError>, code: dao_voting::ErrorCode) {
+    assert_custom_error(result, u32::from(code));
+}
+
+/// Asserts that `result` failed with the custom error `code`, for Anchor's
+/// own constraint errors.
+pub fn assert_custom_error(result: Result<(), BanksClientError>, code: u32) {
+    match result.map_err(|err| err.unwrap()) {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(actual))) => {
+            assert_eq!(actual, code)
+        }
+        Err(err) => panic!("expected custom error {code}, got {err:?}"),
+        Ok(()) => panic!("expected custom error {code}, but the transaction succeeded"),
+    }
+}
+
+/// Leaf of the vault allowlist tree, matching `Vault::check_vote`.
+pub fn allowlist_leaf(user: &Pubkey, allocation: Option<u64>) -> [u8; 32] {
+    match allocation {
+        Some(allocation) => hashv(&[user.as_ref(), &allocation.to_le_bytes()]),
+        None => hashv(&[user.as_ref()]),
+    }
+    .to_bytes()
+}
+
+/// Root of a two-leaf allowlist and the proof for each leaf.
+pub fn allowlist(a: [u8; 32], b: [u8; 32]) -> ([u8; 32], Vec<[u8; 32]>, Vec<[u8; 32]>) {
+    let root = if a <= b {
+        hashv(&[&a, &b])
+    } else {
+        hashv(&[&b, &a])
+    };
+    (root.to_bytes(), vec![b], vec![a])
+}
+
+/// Arguments of `create_st_a_and_vault`, relative to the current time.
+#[derive(Clone)]
+pub struct VaultParams {
+    pub max_vote_cap: u128,
+    pub voting_start: i64,
+    pub deadline: i64,
+    pub max_per_user: Option<u128>,
+    pub min_per_vote: Option<u64>,
+    pub merkle_root: Option<[u8; 32]>,
+    pub max_deadline_extension: i64,
+    pub mode: VaultMode,
+}
+
+impl VaultParams {
+    pub fn new(now: i64) -> Self {
+        Self {
+            max_vote_cap: 1_000_000,
+            voting_start: now,
+            deadline: now + 7 * DAY,
+            max_per_user: None,
+            min_per_vote: None,
+            merkle_root: None,
+            max_deadline_extension: 0,
+            mode: VaultMode::Burn,
+        }
+    }
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub admin: Keypair,
+    pub now: i64,
+}
+
+impl TestEnv {
+    /// Starts a fresh cluster without initializing the program.
+    pub async fn start() -> Self {
+        let mut program_test = ProgramTest::new(
+            "dao_voting",
+            dao_voting::ID,
+            processor!(process_instruction),
+        );
+        program_test.add_account(
+            mpl_token_metadata::ID,
+            Account {
+                lamports: LAMPORTS,
+                data: METAPLEX_PROGRAM.to_vec(),
+                owner: bpf_loader::id(),
+                executable: true,
+                rent_epoch: 0,
+            },
+        );
+        let admin = Keypair::new();
+        program_test.add_account(
+            admin.pubkey(),
+            Account::new(LAMPORTS, 0, &system_program::ID),
+        );
+
+        let context = program_test.start_with_context().await;
+        Self {
+            context,
+            admin,
+            now: START,
+        }
+    }
+
+    /// Starts a fresh cluster with the program initialized by `admin`.
+    pub async fn new() -> Self {
+        let mut env = Self::start().await;
+        let ix = env.initialize_ix(TIMELOCK_DELAY, proposal_config());
+        env.send_admin(&[ix]).await.unwrap();
+        env
+    }
+
+    pub fn warp_to(&mut self, timestamp: i64) {
+        self.now = timestamp;
+    }
+
+    pub fn advance(&mut self, seconds: i64) {
+        self.now += seconds;
+    }
+
+    /// Sends `instructions` signed by `signers`, paid for by the first one.
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let tx = self.transaction(instructions, signers).await;
+        self.context.banks_client.process_transaction(tx).await
+    }
+
+    /// Sends `instructions` signed and paid for by `admin`.
+    pub async fn send_admin(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> Result<(), BanksClientError> {
+        let admin = self.admin.insecure_clone();
+        self.send(instructions, &[&admin]).await
+    }
+
+    /// Simulates a view instruction and decodes its return data.
+    pub async fn view<T: anchor_lang::AnchorDeserialize>(
+        &mut self,
+        instruction: Instruction,
+    ) -> Result<T, TransactionError> {
+        let admin = self.admin.insecure_clone();
+        let tx = self.transaction(&[instruction], &[&admin]).await;
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .unwrap();
+        simulation.result.unwrap()?;
+        let return_data = simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .expect("view instruction returned no data");
+        assert_eq!(return_data.program_id, dao_voting::ID);
+        Ok(T::try_from_slice(&return_data.data).unwrap())
+    }
+
+    /// Moves to a new slot, so identical transactions get fresh signatures,
+    /// and applies the harness clock before building the transaction.
+    async fn transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Transaction {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        self.context.warp_to_slot(clock.slot + 1).unwrap();
+        clock.slot += 1;
+        clock.unix_timestamp = self.now;
+        self.context.set_sysvar(&clock);
+
+        let blockhash = self
+            .context
+            .banks_client
+            .get_latest_blockhash()
+            .await
+            .unwrap();
+        Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            blockhash,
+        )
+    }
+
+    pub async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.context.set_account(
+            &user.pubkey(),
+            &AccountSharedData::new(LAMPORTS, 0, &system_program::ID),
+        );
+        user
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    /// Overwrites a program account in place, to reach states that would
+    /// take billions of transactions, like counters near `u64::MAX`.
+    pub async fn set_program_account<T: AccountSerialize>(&mut self, address: &Pubkey, value: &T) {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut data = Vec::with_capacity(account.data.len());
+        value.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= account.data.len());
+        data.resize(account.data.len(), 0);
+        self.context.set_account(
+            address,
+            &AccountSharedData::from(Account { data, ..account }),
+        );
+    }
+
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("token account {address} does not exist"));
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    pub async fn mint_supply(&mut self, mint: &Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*mint)
+            .await
+            .unwrap()
+            .unwrap();
+        spl_token::state::Mint::unpack(&account.data)
+            .unwrap()
+            .supply
+    }
+
+    /// Creates a 6-decimal mint controlled by `admin`.
+    pub async fn create_mint(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let ixs = [
+            system_instruction::create_account(
+                &self.admin.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &self.admin.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        ];
+        let admin = self.admin.insecure_clone();
+        self.send(&ixs, &[&admin, &mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Mints `amount` of an admin-controlled mint to `owner`'s associated
+    /// token account, creating it if needed.
+    pub async fn mint_to(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let address = ata(owner, mint);
+        let ixs = [
+            create_ata_ix(&self.admin.pubkey(), owner, mint),
+            spl_token::instruction::mint_to(
+                &spl_token::ID,
+                mint,
+                &address,
+                &self.admin.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ];
+        self.send_admin(&ixs).await.unwrap();
+        address
+    }
+
+    /// Creates `owner`'s associated token account for `mint`, if missing.
+    pub async fn create_ata(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let ix = create_ata_ix(&self.admin.pubkey(), owner, mint);
+        self.send_admin(&[ix]).await.unwrap();
+        ata(owner, mint)
+    }
+
+    pub fn initialize_ix(
+        &self,
+        timelock_delay: i64,
+        proposal_config: ProposalConfig,
+    ) -> Instruction {
+        InitializeBuilder::new(
+            self.admin.pubkey(),
+            token_metadata("gov"),
+            token_metadata("stgov"),
+            timelock_delay,
+            proposal_config,
+        )
+        .instruction()
+    }
+
+    pub async fn queued_action_count(&mut self) -> u64 {
+        self.account::<dao_voting::GlobalState>(&global_state())
+            .await
+            .queued_action_count
+    }
+
+    /// Queues `action` as the admin and waits out the timelock, returning
+    /// the queued action id.
+    pub async fn queue(&mut self, action: GovernanceAction) -> u64 {
+        let id = self.queued_action_count().await;
+        let queue = queue_action_ix(&self.admin.pubkey(), id, None, action);
+        self.send_admin(&[queue]).await.unwrap();
+        self.advance(TIMELOCK_DELAY);
+        id
+    }
+
+    /// Queues `action` as the admin, waits out the timelock and executes it.
+    pub async fn govern(&mut self, action: GovernanceAction) {
+        let id = self.queue(action).await;
+        let execute = execute_action_ix(&self.admin.pubkey(), id);
+        self.send_admin(&[execute]).await.unwrap();
+    }
+
+    /// Grants `kind` to `holder` through the timelock, paid for by the admin.
+    pub async fn grant_role(&mut self, kind: RoleKind, holder: &Pubkey) {
+        let id = self
+            .queue(GovernanceAction::GrantRole {
+                kind,
+                holder: *holder,
+            })
+            .await;
+        let admin = self.admin.pubkey();
+        let ix = grant_role_ix(&admin, &admin, id, kind, holder);
+        self.send_admin(&[ix]).await.unwrap();
+    }
+
+    pub async fn set_conversion_config(&mut self, config: ConversionConfig) {
+        self.govern(GovernanceAction::SetConversionConfig { config })
+            .await;
+    }
+
+    pub async fn set_conversion_limits(&mut self, limits: ConversionLimits) {
+        self.govern(GovernanceAction::SetConversionLimits { limits })
+            .await;
+    }
+
+    /// Gives `user` `amount` governance tokens.
+    pub async fn fund_governance(&mut self, user: &Pubkey, amount: u64) -> Pubkey {
+        self.mint_to(&governance_mint(), user, amount).await
+    }
+
+    /// Gives `user` `amount` st_governance tokens by converting at the
+    /// default 1:1 rate.
+    pub async fn fund_st_governance(&mut self, user: &Keypair, amount: u64) -> Pubkey {
+        self.fund_governance(&user.pubkey(), amount).await;
+        let ix = convert_ix(&user.pubkey(), amount, None);
+        self.send(&[ix], &[user]).await.unwrap();
+        ata(&user.pubkey(), &st_governance_mint())
+    }
+
+    pub async fn vault_count(&mut self) -> u64 {
+        self.account::<dao_voting::GlobalState>(&global_state())
+            .await
+            .vault_count
+    }
+
+    /// Creates a vault as the admin and returns its id.
+    pub async fn create_vault(&mut self, params: VaultParams) -> u64 {
+        let vault_id = self.vault_count().await;
+        let ix = create_vault_ix(&self.admin.pubkey(), None, vault_id, params);
+        self.send_admin(&[ix]).await.unwrap();
+        vault_id
+    }
+
+    /// Creates a project token, points `vault_id` at it and deposits
+    /// `amount`, returning the project token mint.
+    pub async fn fund_vault(&mut self, vault_id: u64, convert_time: i64, amount: u64) -> Pubkey {
+        let mint = self.create_mint().await;
+        let admin = self.admin.pubkey();
+        self.mint_to(&mint, &admin, amount).await;
+        let set = set_project_token_ix(&admin, None, vault_id, mint, convert_time);
+        let deposit = deposit_ix(&admin, None, vault_id, &mint, amount);
+        self.send_admin(&[set, deposit]).await.unwrap();
+        mint
+    }
+
+    pub async fn vote(
+        &mut self,
+        user: &Keypair,
+        vault_id: u64,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let ix = vote_ix(&user.pubkey(), vault_id, amount, vec![], None);
+        self.send(&[ix], &[user]).await
+    }
+}
+
+pub fn proposal_config() -> ProposalConfig {
+    ProposalConfig {
+        voting_period: VOTING_PERIOD,
+        quorum: QUORUM,
+        threshold_bps: 5_000,
+    }
+}
+
+pub fn create_ata_ix(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        payer,
+        owner,
+        mint,
+        &spl_token::ID,
+    )
+}
+
+pub fn grant_role_ix(
+    payer: &Pubkey,
+    proposer: &Pubkey,
+    id: u64,
+    kind: RoleKind,
+    holder: &Pubkey,
+) -> Instruction {
+    GrantRoleBuilder::new(*payer, *proposer, id, kind, *holder).instruction()
+}
+
+pub fn revoke_role_ix(proposer: &Pubkey, id: u64, role: Pubkey, granted_by: &Pubkey) -> Instruction {
+    RevokeRoleBuilder::new(*proposer, id, role, *granted_by).instruction()
+}
+
+pub fn pause_ix(pauser: &Pubkey, role: Option<Pubkey>) -> Instruction {
+    with_role(PauseBuilder::new(*pauser), role, PauseBuilder::role).instruction()
+}
+
+pub fn queue_action_ix(
+    proposer: &Pubkey,
+    id: u64,
+    role: Option<Pubkey>,
+    action: GovernanceAction,
+) -> Instruction {
+    with_role(
+        QueueActionBuilder::new(*proposer, id, action),
+        role,
+        QueueActionBuilder::role,
+    )
+    .instruction()
+}
+
+pub fn execute_action_ix(proposer: &Pubkey, id: u64) -> Instruction {
+    ExecuteActionBuilder::new(*proposer, id).instruction()
+}
+
+pub fn cancel_action_ix(
+    authority: &Pubkey,
+    role: Option<Pubkey>,
+    proposer: &Pubkey,
+    id: u64,
+) -> Instruction {
+    with_role(
+        CancelActionBuilder::new(*authority, *proposer, id),
+        role,
+        CancelActionBuilder::role,
+    )
+    .instruction()
+}
+
+pub fn create_proposal_ix(proposer: &Pubkey, id: u64, action: GovernanceAction) -> Instruction {
+    CreateProposalBuilder::new(*proposer, id, action).instruction()
+}
+
+pub fn cast_proposal_vote_ix(
+    voter: &Pubkey,
+    id: u64,
+    choice: dao_voting::VoteChoice,
+    amount: u64,
+) -> Instruction {
+    CastProposalVoteBuilder::new(*voter, id, choice, amount).instruction()
+}
+
+pub fn withdraw_proposal_vote_ix(voter: &Pubkey, id: u64) -> Instruction {
+    WithdrawProposalVoteBuilder::new(*voter, id).instruction()
+}
+
+pub fn execute_proposal_ix(payer: &Pubkey, id: u64, queued_action_id: u64) -> Instruction {
+    ExecuteProposalBuilder::new(*payer, id, queued_action_id).instruction()
+}
+
+/// `treasury` is the owner of the fee account.
+pub fn convert_ix(user: &Pubkey, amount: u64, treasury: Option<Pubkey>) -> Instruction {
+    let builder = ConvertToStGovernanceBuilder::new(*user, amount);
+    match treasury {
+        Some(treasury) => builder.treasury(treasury),
+        None => builder,
+    }
+    .instruction()
+}
+
+pub fn create_vault_ix(
+    creator: &Pubkey,
+    role: Option<Pubkey>,
+    vault_id: u64,
+    params: VaultParams,
+) -> Instruction {
+    let mut builder = CreateStAAndVaultBuilder::new(
+        *creator,
+        vault_id,
+        token_metadata("vote"),
+        params.max_vote_cap,
+        params.voting_start,
+        params.deadline,
+    )
+    .max_deadline_extension(params.max_deadline_extension)
+    .mode(params.mode);
+    if let Some(max_per_user) = params.max_per_user {
+        builder = builder.max_per_user(max_per_user);
+    }
+    if let Some(min_per_vote) = params.min_per_vote {
+        builder = builder.min_per_vote(min_per_vote);
+    }
+    if let Some(merkle_root) = params.merkle_root {
+        builder = builder.merkle_root(merkle_root);
+    }
+    with_role(builder, role, CreateStAAndVaultBuilder::role).instruction()
+}
+
+pub fn vote_ix(
+    user: &Pubkey,
+    vault_id: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+    allocation: Option<u64>,
+) -> Instruction {
+    let builder = VoteBuilder::new(*user, vault_id, amount).proof(proof);
+    match allocation {
+        Some(allocation) => builder.allocation(allocation),
+        None => builder,
+    }
+    .instruction()
+}
+
+pub fn lock_vote_ix(user: &Pubkey, vault_id: u64, amount: u64) -> Instruction {
+    LockVoteBuilder::new(*user, vault_id, amount).instruction()
+}
+
+pub fn unlock_vote_ix(user: &Pubkey, vault_id: u64) -> Instruction {
+    UnlockVoteBuilder::new(*user, vault_id).instruction()
+}
+
+pub fn stake_ix(owner: &Pubkey, amount: u64) -> Instruction {
+    StakeStGovernanceBuilder::new(*owner, amount).instruction()
+}
+
+pub fn unstake_ix(owner: &Pubkey, amount: u64) -> Instruction {
+    UnstakeStGovernanceBuilder::new(*owner, amount).instruction()
+}
+
+pub fn claim_staking_rewards_ix(owner: &Pubkey) -> Instruction {
+    ClaimStakingRewardsBuilder::new(*owner).instruction()
+}
+
+pub fn fund_rewards_ix(
+    treasurer: &Pubkey,
+    role: Option<Pubkey>,
+    amount: u64,
+    duration: i64,
+) -> Instruction {
+    with_role(
+        FundRewardsBuilder::new(*treasurer, amount, duration),
+        role,
+        FundRewardsBuilder::role,
+    )
+    .instruction()
+}
+
+pub fn create_ve_lock_ix(owner: &Pubkey, amount: u64, duration: i64) -> Instruction {
+    CreateVeLockBuilder::new(*owner, amount, duration).instruction()
+}
+
+pub fn withdraw_ve_lock_ix(owner: &Pubkey) -> Instruction {
+    WithdrawVeLockBuilder::new(*owner).instruction()
+}
+
+pub fn ve_vote_ix(user: &Pubkey, vault_id: u64) -> Instruction {
+    VeVoteBuilder::new(*user, vault_id).instruction()
+}
+
+pub fn set_project_token_ix(
+    owner: &Pubkey,
+    role: Option<Pubkey>,
+    vault_id: u64,
+    project_token_mint: Pubkey,
+    convert_time: i64,
+) -> Instruction {
+    with_role(
+        SetProjectTokenBuilder::new(*owner, vault_id, project_token_mint, convert_time),
+        role,
+        SetProjectTokenBuilder::role,
+    )
+    .instruction()
+}
+
+pub fn set_merkle_root_ix(
+    owner: &Pubkey,
+    vault_id: u64,
+    merkle_root: Option<[u8; 32]>,
+) -> Instruction {
+    SetMerkleRootBuilder::new(*owner, vault_id, merkle_root).instruction()
+}
+
+pub fn update_voting_window_ix(
+    owner: &Pubkey,
+    vault_id: u64,
+    voting_start: i64,
+    deadline: i64,
+) -> Instruction {
+    UpdateVotingWindowBuilder::new(*owner, vault_id, voting_start, deadline).instruction()
+}
+
+pub fn update_vault_schedule_ix(
+    owner: &Pubkey,
+    vault_id: u64,
+    update: dao_voting::ScheduleUpdate,
+) -> Instruction {
+    UpdateVaultScheduleBuilder::new(*owner, vault_id, update).instruction()
+}
+
+pub fn update_max_vote_cap_ix(
+    owner: &Pubkey,
+    vault_id: u64,
+    new_max_vote_cap: u128,
+) -> Instruction {
+    UpdateMaxVoteCapBuilder::new(*owner, vault_id, new_max_vote_cap).instruction()
+}
+
+pub fn deposit_ix(
+    project_authority: &Pubkey,
+    role: Option<Pubkey>,
+    vault_id: u64,
+    project_token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    with_role(
+        DepositProjectTokensBuilder::new(*project_authority, vault_id, *project_token_mint, amount),
+        role,
+        DepositProjectTokensBuilder::role,
+    )
+    .instruction()
+}
+
+pub fn claim_ix(user: &Pubkey, vault_id: u64, project_token_mint: &Pubkey) -> Instruction {
+    ClaimProjectTokensBuilder::new(*user, vault_id, *project_token_mint).instruction()
+}
+
+pub fn quote_claim_ix(user: &Pubkey, vault_id: u64) -> Instruction {
+    QuoteClaimBuilder::new(*user, vault_id).instruction()
+}
+
+/// `voted` passes the user's `UserVault`, which only exists once they voted.
+pub fn quote_vote_ix(user: &Pubkey, vault_id: u64, amount: u64, voted: bool) -> Instruction {
+    let builder = QuoteVoteBuilder::new(*user, vault_id, amount);
+    if voted {
+        builder.with_user_vault()
+    } else {
+        builder
+    }
+    .instruction()
+}
+
+pub fn vault_summary_ix(vault_id: u64) -> Instruction {
+    VaultSummaryBuilder::new(vault_id).instruction()
+}
+
+pub fn migrate_global_state_ix(payer: &Pubkey) -> Instruction {
+    MigrateGlobalStateBuilder::new(*payer).instruction()
+}
+
+pub fn migrate_vault_ix(payer: &Pubkey, vault_id: u64) -> Instruction {
+    MigrateVaultBuilder::new(*payer, vault_id).instruction()
+}
+
+pub fn migrate_user_vault_ix(payer: &Pubkey, vault_id: u64, user: &Pubkey) -> Instruction {
+    MigrateUserVaultBuilder::new(*payer, vault_id, *user).instruction()
+}
+
+/// Applies an explicit role account, which may be of the wrong kind or
+/// belong to someone else, for the tests that check role handling.
+fn with_role<B>(builder: B, role: Option<Pubkey>, set: fn(B, Pubkey) -> B) -> B {
+    match role {
+        Some(role) => set(builder, role),
+        None => builder,
+    }
+}
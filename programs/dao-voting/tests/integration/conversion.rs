@@ -0,0 +1,202 @@
+use dao_voting::{
+    pda, ConversionConfig, ConversionLimits, ConversionWindow, ErrorCode, GovernanceAction,
+};
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+#[tokio::test]
+async fn converts_one_to_one_by_default() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let governance_account = env.fund_governance(&user.pubkey(), 500).await;
+
+    let ix = convert_ix(&user.pubkey(), 200, None);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    assert_eq!(env.token_balance(&governance_account).await, 300);
+    let st_account = ata(&user.pubkey(), &st_governance_mint());
+    assert_eq!(env.token_balance(&st_account).await, 200);
+    assert_eq!(env.mint_supply(&governance_mint()).await, 300);
+    assert_eq!(env.mint_supply(&st_governance_mint()).await, 200);
+
+    let window_address = pda::find_user_conversion_window_address(&user.pubkey()).0;
+    let window: ConversionWindow = env.account(&window_address).await;
+    assert_eq!(window.total(), 200);
+
+    let ix = convert_ix(&user.pubkey(), 0, None);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::ConversionTooSmall,
+    );
+}
+
+#[tokio::test]
+async fn converts_at_the_configured_rate_with_a_treasury_fee() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let treasury = env.new_user().await.pubkey();
+    env.set_conversion_config(ConversionConfig {
+        rate_numerator: 3,
+        rate_denominator: 2,
+        fee_bps: 250,
+        treasury,
+    })
+    .await;
+    let governance_account = env.fund_governance(&user.pubkey(), 1_000).await;
+    let treasury_account = env.create_ata(&treasury, &governance_mint()).await;
+
+    let ix = convert_ix(&user.pubkey(), 101, None);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InvalidTreasuryAccount,
+    );
+    // The fee account must belong to the configured treasury
+    let ix = convert_ix(&user.pubkey(), 101, Some(user.pubkey()));
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InvalidTreasuryAccount,
+    );
+
+    // 2 of 101 goes to the treasury, 99 is burned for floor(99 * 3 / 2)
+    let ix = convert_ix(&user.pubkey(), 101, Some(treasury));
+    env.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(env.token_balance(&treasury_account).await, 2);
+    assert_eq!(env.token_balance(&governance_account).await, 899);
+    assert_eq!(env.mint_supply(&governance_mint()).await, 901);
+    let st_account = ata(&user.pubkey(), &st_governance_mint());
+    assert_eq!(env.token_balance(&st_account).await, 148);
+}
+
+#[tokio::test]
+async fn rejects_invalid_conversion_terms() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let user = env.new_user().await;
+
+    let config = ConversionConfig {
+        rate_numerator: 1,
+        rate_denominator: 0,
+        fee_bps: 0,
+        treasury: admin,
+    };
+    let ix = queue_action_ix(
+        &admin,
+        0,
+        None,
+        GovernanceAction::SetConversionConfig { config },
+    );
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::InvalidConversionConfig,
+    );
+
+    let limits = ConversionLimits {
+        window: 0,
+        global_cap: 0,
+        per_user_cap: 0,
+    };
+    let ix = queue_action_ix(
+        &admin,
+        0,
+        None,
+        GovernanceAction::SetConversionLimits { limits },
+    );
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::InvalidConversionLimits,
+    );
+
+    env.set_conversion_config(ConversionConfig {
+        rate_numerator: u64::MAX,
+        rate_denominator: 1,
+        fee_bps: 0,
+        treasury: admin,
+    })
+    .await;
+    env.fund_governance(&user.pubkey(), 2).await;
+    let ix = convert_ix(&user.pubkey(), 2, None);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::ConversionOverflow,
+    );
+}
+
+#[tokio::test]
+async fn caps_conversions_per_window_globally_and_per_user() {
+    let mut env = TestEnv::new().await;
+    let first = env.new_user().await;
+    let second = env.new_user().await;
+    env.set_conversion_limits(ConversionLimits {
+        window: DAY,
+        global_cap: 100,
+        per_user_cap: 60,
+    })
+    .await;
+    env.fund_governance(&first.pubkey(), 200).await;
+    env.fund_governance(&second.pubkey(), 200).await;
+
+    let ix = convert_ix(&first.pubkey(), 60, None);
+    env.send(&[ix], &[&first]).await.unwrap();
+    let ix = convert_ix(&first.pubkey(), 1, None);
+    assert_error(
+        env.send(&[ix], &[&first]).await,
+        ErrorCode::ConversionCapExceeded,
+    );
+
+    let ix = convert_ix(&second.pubkey(), 40, None);
+    env.send(&[ix], &[&second]).await.unwrap();
+    let ix = convert_ix(&second.pubkey(), 1, None);
+    assert_error(
+        env.send(&[ix], &[&second]).await,
+        ErrorCode::ConversionCapExceeded,
+    );
+
+    let window_address = pda::find_global_conversion_window_address().0;
+    let window: ConversionWindow = env.account(&window_address).await;
+    assert_eq!(window.total(), 100);
+
+    // Both caps free up once the conversions leave the window
+    env.advance(2 * DAY);
+    let ix = convert_ix(&first.pubkey(), 60, None);
+    env.send(&[ix], &[&first]).await.unwrap();
+    let window: ConversionWindow = env.account(&window_address).await;
+    assert_eq!(window.total(), 60);
+}
+
+#[tokio::test]
+async fn keeps_the_cap_across_day_boundaries() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    env.set_conversion_limits(ConversionLimits {
+        window: DAY,
+        global_cap: 0,
+        per_user_cap: 60,
+    })
+    .await;
+    env.fund_governance(&user.pubkey(), 200).await;
+
+    // Convert the whole cap just before midnight
+    env.warp_to((env.now / DAY + 1) * DAY - 60);
+    let ix = convert_ix(&user.pubkey(), 60, None);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    // Crossing midnight does not reset the cap
+    env.advance(120);
+    let ix = convert_ix(&user.pubkey(), 1, None);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::ConversionCapExceeded,
+    );
+    env.advance(DAY - 3 * 60 * 60);
+    let ix = convert_ix(&user.pubkey(), 1, None);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::ConversionCapExceeded,
+    );
+
+    // A full window after the conversion the cap is available again
+    env.advance(3 * 60 * 60);
+    let ix = convert_ix(&user.pubkey(), 60, None);
+    env.send(&[ix], &[&user]).await.unwrap();
+}
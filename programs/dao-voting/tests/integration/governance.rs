@@ -0,0 +1,494 @@
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use dao_voting::{
+    pda, ErrorCode, GlobalState, GovernanceAction, Proposal, ProposalConfig, ProposalVote,
+    QueuedAction, Role, RoleKind, VaultMode, VoteChoice,
+};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+async fn lamports(env: &mut TestEnv, address: &Pubkey) -> u64 {
+    env.context
+        .banks_client
+        .get_balance(*address)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn initializes_global_state() {
+    let mut env = TestEnv::new().await;
+
+    let state: GlobalState = env.account(&global_state()).await;
+    assert_eq!(state.admin, env.admin.pubkey());
+    assert_eq!(state.governance_token_mint, governance_mint());
+    assert_eq!(state.st_governance_token_mint, st_governance_mint());
+    assert_eq!(state.vault_count, 0);
+    assert!(!state.paused);
+    assert_eq!(state.timelock_delay, TIMELOCK_DELAY);
+    assert_eq!(state.proposal_config, proposal_config());
+    assert_eq!(state.conversion_config.rate_numerator, 1);
+    assert_eq!(state.conversion_config.rate_denominator, 1);
+    assert_eq!(state.conversion_config.treasury, env.admin.pubkey());
+    assert_eq!(state.conversion_limits.window, DAY);
+    assert!(env.exists(&metadata_address(&governance_mint())).await);
+    assert!(env.exists(&metadata_address(&st_governance_mint())).await);
+}
+
+#[tokio::test]
+async fn rejects_invalid_initial_config() {
+    let mut env = TestEnv::start().await;
+
+    let ix = env.initialize_ix(-1, proposal_config());
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::InvalidTimelockDelay);
+
+    let config = ProposalConfig {
+        threshold_bps: 10_001,
+        ..proposal_config()
+    };
+    let ix = env.initialize_ix(TIMELOCK_DELAY, config);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::InvalidProposalConfig,
+    );
+}
+
+#[tokio::test]
+async fn grants_and_revokes_roles_through_the_timelock() {
+    let mut env = TestEnv::new().await;
+    let operator = env.new_user().await;
+    let delegate = env.new_user().await;
+    let admin = env.admin.pubkey();
+
+    env.grant_role(RoleKind::Operator, &operator.pubkey()).await;
+    let operator_role = role_address(RoleKind::Operator, &operator.pubkey());
+    let role: Role = env.account(&operator_role).await;
+    assert_eq!(role.holder, operator.pubkey());
+    assert_eq!(role.kind, RoleKind::Operator);
+    assert_eq!(role.granted_by, admin);
+
+    // Grants wait out the timelock and only create what was queued
+    let id = env.queued_action_count().await;
+    let grant_pauser = GovernanceAction::GrantRole {
+        kind: RoleKind::Pauser,
+        holder: operator.pubkey(),
+    };
+    let ix = queue_action_ix(&admin, id, None, grant_pauser);
+    env.send_admin(&[ix]).await.unwrap();
+    let ix = grant_role_ix(
+        &delegate.pubkey(),
+        &admin,
+        id,
+        RoleKind::Pauser,
+        &operator.pubkey(),
+    );
+    assert_error(
+        env.send(std::slice::from_ref(&ix), &[&delegate]).await,
+        ErrorCode::TimelockNotExpired,
+    );
+    env.advance(TIMELOCK_DELAY);
+    let wrong_kind = grant_role_ix(
+        &delegate.pubkey(),
+        &admin,
+        id,
+        RoleKind::Treasurer,
+        &operator.pubkey(),
+    );
+    assert_error(
+        env.send(&[wrong_kind], &[&delegate]).await,
+        ErrorCode::WrongAction,
+    );
+    // Role changes need their accounts, which execute_action doesn't take
+    let ix_execute = execute_action_ix(&admin, id);
+    assert_error(env.send_admin(&[ix_execute]).await, ErrorCode::WrongAction);
+    env.send(&[ix], &[&delegate]).await.unwrap();
+    let pauser_role = role_address(RoleKind::Pauser, &operator.pubkey());
+    let role: Role = env.account(&pauser_role).await;
+    assert_eq!(role.granted_by, delegate.pubkey());
+    assert!(!env.exists(&pda::find_queued_action_address(id).0).await);
+
+    // Only admins queue role changes
+    let id = env.queued_action_count().await;
+    let action = GovernanceAction::GrantRole {
+        kind: RoleKind::Pauser,
+        holder: delegate.pubkey(),
+    };
+    let ix = queue_action_ix(&operator.pubkey(), id, Some(operator_role), action);
+    assert_error(env.send(&[ix], &[&operator]).await, ErrorCode::Unauthorized);
+
+    // Admin role holders can't grant or revoke the admin role itself
+    env.grant_role(RoleKind::Admin, &delegate.pubkey()).await;
+    let delegate_role = role_address(RoleKind::Admin, &delegate.pubkey());
+    let id = env.queued_action_count().await;
+    for action in [
+        GovernanceAction::GrantRole {
+            kind: RoleKind::Admin,
+            holder: operator.pubkey(),
+        },
+        GovernanceAction::RevokeRole {
+            kind: RoleKind::Admin,
+            holder: delegate.pubkey(),
+        },
+    ] {
+        let ix = queue_action_ix(&delegate.pubkey(), id, Some(delegate_role), action);
+        assert_error(env.send(&[ix], &[&delegate]).await, ErrorCode::Unauthorized);
+    }
+
+    // The admin role covers every other role
+    let vault_id = env.vault_count().await;
+    let ix = create_vault_ix(
+        &delegate.pubkey(),
+        Some(delegate_role),
+        vault_id,
+        VaultParams::new(env.now),
+    );
+    env.send(&[ix], &[&delegate]).await.unwrap();
+
+    // Revocations wait out the timelock too
+    let id = env.queued_action_count().await;
+    let action = GovernanceAction::RevokeRole {
+        kind: RoleKind::Operator,
+        holder: operator.pubkey(),
+    };
+    let ix = queue_action_ix(&delegate.pubkey(), id, Some(delegate_role), action);
+    env.send(&[ix], &[&delegate]).await.unwrap();
+    let ix = revoke_role_ix(&delegate.pubkey(), id, operator_role, &admin);
+    assert_error(
+        env.send_admin(std::slice::from_ref(&ix)).await,
+        ErrorCode::TimelockNotExpired,
+    );
+    env.advance(TIMELOCK_DELAY);
+    let wrong_role = revoke_role_ix(&delegate.pubkey(), id, pauser_role, &delegate.pubkey());
+    assert_error(env.send_admin(&[wrong_role]).await, ErrorCode::WrongAction);
+    let rent = lamports(&mut env, &operator_role).await;
+    let before = lamports(&mut env, &admin).await;
+    env.send(&[ix], &[&delegate]).await.unwrap();
+    assert!(!env.exists(&operator_role).await);
+    assert_eq!(lamports(&mut env, &admin).await, before + rent);
+
+    // Rent goes back to whoever paid for the role, not the admin
+    let id = env
+        .queue(GovernanceAction::RevokeRole {
+            kind: RoleKind::Pauser,
+            holder: operator.pubkey(),
+        })
+        .await;
+    let ix = revoke_role_ix(&admin, id, pauser_role, &admin);
+    assert_custom_error(
+        env.send_admin(&[ix]).await,
+        AnchorErrorCode::ConstraintAddress.into(),
+    );
+    let rent = lamports(&mut env, &pauser_role).await;
+    let before = lamports(&mut env, &delegate.pubkey()).await;
+    let ix = revoke_role_ix(&admin, id, pauser_role, &delegate.pubkey());
+    env.send_admin(&[ix]).await.unwrap();
+    assert!(!env.exists(&pauser_role).await);
+    assert_eq!(lamports(&mut env, &delegate.pubkey()).await, before + rent);
+
+    let id = env
+        .queue(GovernanceAction::RevokeRole {
+            kind: RoleKind::Admin,
+            holder: delegate.pubkey(),
+        })
+        .await;
+    let ix = revoke_role_ix(&admin, id, delegate_role, &admin);
+    env.send_admin(&[ix]).await.unwrap();
+    assert!(!env.exists(&delegate_role).await);
+}
+
+#[tokio::test]
+async fn pauses_with_the_pauser_role() {
+    let mut env = TestEnv::new().await;
+    let pauser = env.new_user().await;
+
+    let ix = pause_ix(&pauser.pubkey(), None);
+    assert_error(env.send(&[ix], &[&pauser]).await, ErrorCode::Unauthorized);
+
+    // A role of another kind doesn't count
+    env.grant_role(RoleKind::Operator, &pauser.pubkey()).await;
+    let operator_role = role_address(RoleKind::Operator, &pauser.pubkey());
+    let ix = pause_ix(&pauser.pubkey(), Some(operator_role));
+    assert_error(env.send(&[ix], &[&pauser]).await, ErrorCode::Unauthorized);
+
+    env.grant_role(RoleKind::Pauser, &pauser.pubkey()).await;
+    let pauser_role = role_address(RoleKind::Pauser, &pauser.pubkey());
+    let ix = pause_ix(&pauser.pubkey(), Some(pauser_role));
+    env.send(&[ix], &[&pauser]).await.unwrap();
+    assert!(env.account::<GlobalState>(&global_state()).await.paused);
+
+    // Unpausing goes through the timelock
+    env.govern(GovernanceAction::SetPaused { paused: false })
+        .await;
+    assert!(!env.account::<GlobalState>(&global_state()).await.paused);
+}
+
+#[tokio::test]
+async fn pause_blocks_user_actions() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let locker = env.new_user().await;
+    let burn_vault = env.create_vault(VaultParams::new(env.now)).await;
+    let lock_vault = env
+        .create_vault(VaultParams {
+            mode: VaultMode::Lock,
+            ..VaultParams::new(env.now)
+        })
+        .await;
+    let convert_time = env.now + 8 * DAY;
+    let project_mint = env.fund_vault(burn_vault, convert_time, 1_000).await;
+
+    env.fund_st_governance(&user, 100).await;
+    env.fund_governance(&user.pubkey(), 100).await;
+    env.fund_governance(&locker.pubkey(), 100).await;
+    let setup = [
+        vote_ix(&user.pubkey(), burn_vault, 10, vec![], None),
+        stake_ix(&user.pubkey(), 10),
+        create_ve_lock_ix(&user.pubkey(), 50, DAY),
+    ];
+    env.send(&setup, &[&user]).await.unwrap();
+    env.create_ata(&global_state(), &governance_mint()).await;
+
+    env.send_admin(&[pause_ix(&env.admin.pubkey(), None)])
+        .await
+        .unwrap();
+
+    let blocked = [
+        convert_ix(&user.pubkey(), 10, None),
+        vote_ix(&user.pubkey(), burn_vault, 10, vec![], None),
+        lock_vote_ix(&user.pubkey(), lock_vault, 10),
+        ve_vote_ix(&user.pubkey(), burn_vault),
+        stake_ix(&user.pubkey(), 10),
+        claim_staking_rewards_ix(&user.pubkey()),
+    ];
+    for ix in blocked {
+        assert_error(env.send(&[ix], &[&user]).await, ErrorCode::Paused);
+    }
+    let ix = create_ve_lock_ix(&locker.pubkey(), 50, DAY);
+    assert_error(env.send(&[ix], &[&locker]).await, ErrorCode::Paused);
+    let ix = deposit_ix(&env.admin.pubkey(), None, burn_vault, &project_mint, 0);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::Paused);
+    let ix = quote_vote_ix(&user.pubkey(), burn_vault, 10, false);
+    assert_eq!(
+        env.view::<dao_voting::quote::VoteQuote>(ix).await,
+        Err(custom_error(ErrorCode::Paused)),
+    );
+
+    env.warp_to(convert_time);
+    let ix = claim_ix(&user.pubkey(), burn_vault, &project_mint);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::Paused);
+
+    // Stakers can always leave
+    let ix = unstake_ix(&user.pubkey(), 10);
+    env.send(&[ix], &[&user]).await.unwrap();
+}
+
+#[tokio::test]
+async fn executes_queued_actions_after_the_timelock() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let new_admin = env.new_user().await.pubkey();
+    let outsider = env.new_user().await;
+
+    let action = GovernanceAction::TransferAdmin { new_admin };
+    let ix = queue_action_ix(&outsider.pubkey(), 0, None, action.clone());
+    assert_error(env.send(&[ix], &[&outsider]).await, ErrorCode::Unauthorized);
+    let ix = queue_action_ix(
+        &admin,
+        0,
+        None,
+        GovernanceAction::SetTimelockDelay { delay: -1 },
+    );
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::InvalidTimelockDelay);
+
+    let ix = queue_action_ix(&admin, 0, None, action.clone());
+    env.send_admin(&[ix]).await.unwrap();
+    let queued_action_address = pda::find_queued_action_address(0).0;
+    let queued: QueuedAction = env.account(&queued_action_address).await;
+    assert_eq!(queued.proposer, admin);
+    assert_eq!(queued.eta, env.now + TIMELOCK_DELAY);
+    assert_eq!(queued.action, action);
+
+    env.advance(TIMELOCK_DELAY - 1);
+    let ix = execute_action_ix(&admin, 0);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::TimelockNotExpired);
+
+    env.advance(1);
+    let ix = execute_action_ix(&admin, 0);
+    env.send_admin(&[ix]).await.unwrap();
+    let state: GlobalState = env.account(&global_state()).await;
+    assert_eq!(state.admin, new_admin);
+    assert_eq!(state.queued_action_count, 1);
+    assert!(!env.exists(&queued_action_address).await);
+}
+
+#[tokio::test]
+async fn cancels_queued_actions() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let outsider = env.new_user().await;
+
+    let ix = queue_action_ix(
+        &admin,
+        0,
+        None,
+        GovernanceAction::SetPaused { paused: true },
+    );
+    env.send_admin(&[ix]).await.unwrap();
+
+    let ix = cancel_action_ix(&outsider.pubkey(), None, &admin, 0);
+    assert_error(env.send(&[ix], &[&outsider]).await, ErrorCode::Unauthorized);
+
+    let ix = cancel_action_ix(&admin, None, &admin, 0);
+    env.send_admin(&[ix]).await.unwrap();
+    assert!(!env.exists(&pda::find_queued_action_address(0).0).await);
+
+    env.advance(TIMELOCK_DELAY);
+    let ix = execute_action_ix(&admin, 0);
+    assert_custom_error(
+        env.send_admin(&[ix]).await,
+        anchor_lang::error::ErrorCode::AccountNotInitialized.into(),
+    );
+    assert!(!env.account::<GlobalState>(&global_state()).await.paused);
+}
+
+#[tokio::test]
+async fn executes_passed_proposals() {
+    let mut env = TestEnv::new().await;
+    let yes = env.new_user().await;
+    let no = env.new_user().await;
+    let yes_account = env.fund_st_governance(&yes, 80).await;
+    env.fund_st_governance(&no, 30).await;
+
+    let invalid = GovernanceAction::SetProposalConfig {
+        config: ProposalConfig {
+            voting_period: 0,
+            ..proposal_config()
+        },
+    };
+    let ix = create_proposal_ix(&yes.pubkey(), 0, invalid);
+    assert_error(
+        env.send(&[ix], &[&yes]).await,
+        ErrorCode::InvalidProposalConfig,
+    );
+
+    let action = GovernanceAction::SetTimelockDelay { delay: 2 * DAY };
+    let ix = create_proposal_ix(&yes.pubkey(), 0, action.clone());
+    env.send(&[ix], &[&yes]).await.unwrap();
+    let proposal_address = pda::find_proposal_address(0).0;
+    let proposal: Proposal = env.account(&proposal_address).await;
+    assert_eq!(proposal.proposer, yes.pubkey());
+    assert_eq!(proposal.action, action);
+    assert_eq!(proposal.voting_end, env.now + VOTING_PERIOD);
+    assert_eq!(proposal.quorum, QUORUM);
+
+    let ix = cast_proposal_vote_ix(&yes.pubkey(), 0, VoteChoice::Yes, 50);
+    env.send(&[ix], &[&yes]).await.unwrap();
+    let ix = cast_proposal_vote_ix(&yes.pubkey(), 0, VoteChoice::No, 10);
+    assert_error(
+        env.send(&[ix], &[&yes]).await,
+        ErrorCode::VoteChoiceMismatch,
+    );
+    let ix = cast_proposal_vote_ix(&yes.pubkey(), 0, VoteChoice::Yes, 30);
+    env.send(&[ix], &[&yes]).await.unwrap();
+    let ix = cast_proposal_vote_ix(&no.pubkey(), 0, VoteChoice::No, 20);
+    env.send(&[ix], &[&no]).await.unwrap();
+
+    let proposal: Proposal = env.account(&proposal_address).await;
+    assert_eq!((proposal.yes_votes, proposal.no_votes), (80, 20));
+    assert_eq!(env.token_balance(&yes_account).await, 0);
+    let escrow = ata(&proposal_address, &st_governance_mint());
+    assert_eq!(env.token_balance(&escrow).await, 100);
+
+    let admin = env.admin.pubkey();
+    let queued_id = env
+        .account::<GlobalState>(&global_state())
+        .await
+        .queued_action_count;
+    let ix = execute_proposal_ix(&admin, 0, queued_id);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::ProposalVotingActive);
+    let ix = withdraw_proposal_vote_ix(&yes.pubkey(), 0);
+    assert_error(
+        env.send(&[ix], &[&yes]).await,
+        ErrorCode::ProposalVotingActive,
+    );
+
+    env.advance(VOTING_PERIOD);
+    let ix = cast_proposal_vote_ix(&no.pubkey(), 0, VoteChoice::No, 10);
+    assert_error(
+        env.send(&[ix], &[&no]).await,
+        ErrorCode::ProposalVotingEnded,
+    );
+
+    // Passing only queues the action behind the timelock
+    let ix = execute_proposal_ix(&admin, 0, queued_id);
+    env.send_admin(&[ix]).await.unwrap();
+    assert!(env.account::<Proposal>(&proposal_address).await.executed);
+    let queued: QueuedAction = env
+        .account(&pda::find_queued_action_address(queued_id).0)
+        .await;
+    assert_eq!(queued.action, action);
+    assert_eq!(queued.eta, env.now + TIMELOCK_DELAY);
+    let ix = execute_proposal_ix(&admin, 0, queued_id + 1);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::ProposalAlreadyExecuted,
+    );
+
+    let ix = execute_action_ix(&admin, queued_id);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::TimelockNotExpired);
+    let state: GlobalState = env.account(&global_state()).await;
+    assert_eq!(state.timelock_delay, TIMELOCK_DELAY);
+
+    env.advance(TIMELOCK_DELAY);
+    let ix = execute_action_ix(&admin, queued_id);
+    env.send_admin(&[ix]).await.unwrap();
+    let state: GlobalState = env.account(&global_state()).await;
+    assert_eq!(state.timelock_delay, 2 * DAY);
+
+    let ix = withdraw_proposal_vote_ix(&yes.pubkey(), 0);
+    env.send(&[ix], &[&yes]).await.unwrap();
+    assert_eq!(env.token_balance(&yes_account).await, 80);
+    let vote_address = pda::find_proposal_vote_address(&proposal_address, &yes.pubkey()).0;
+    assert!(!env.exists(&vote_address).await);
+}
+
+#[tokio::test]
+async fn rejects_proposals_without_quorum_or_majority() {
+    let mut env = TestEnv::new().await;
+    let yes = env.new_user().await;
+    let no = env.new_user().await;
+    env.fund_st_governance(&yes, 120).await;
+    env.fund_st_governance(&no, 60).await;
+
+    let action = GovernanceAction::SetPaused { paused: true };
+    for id in 0..2 {
+        let ix = create_proposal_ix(&yes.pubkey(), id, action.clone());
+        env.send(&[ix], &[&yes]).await.unwrap();
+    }
+
+    // Proposal 0 passes the threshold but misses the quorum
+    let ix = cast_proposal_vote_ix(&yes.pubkey(), 0, VoteChoice::Yes, 60);
+    env.send(&[ix], &[&yes]).await.unwrap();
+    // Proposal 1 reaches the quorum with a tie, which isn't a majority
+    let ix = cast_proposal_vote_ix(&yes.pubkey(), 1, VoteChoice::Yes, 60);
+    env.send(&[ix], &[&yes]).await.unwrap();
+    let ix = cast_proposal_vote_ix(&no.pubkey(), 1, VoteChoice::No, 60);
+    env.send(&[ix], &[&no]).await.unwrap();
+    let proposal: ProposalVote = env
+        .account(&pda::find_proposal_vote_address(&pda::find_proposal_address(1).0, &no.pubkey()).0)
+        .await;
+    assert_eq!((proposal.choice, proposal.amount), (VoteChoice::No, 60));
+
+    env.advance(VOTING_PERIOD);
+    let admin = env.admin.pubkey();
+    assert_error(
+        env.send_admin(&[execute_proposal_ix(&admin, 0, 0)]).await,
+        ErrorCode::QuorumNotReached,
+    );
+    assert_error(
+        env.send_admin(&[execute_proposal_ix(&admin, 1, 0)]).await,
+        ErrorCode::ProposalRejected,
+    );
+    assert!(!env.account::<GlobalState>(&global_state()).await.paused);
+}
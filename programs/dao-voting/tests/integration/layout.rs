@@ -0,0 +1,57 @@
+//! Allocated account space against the largest serialization of each
+//! account, so a new field can't outgrow its account unnoticed.
+
+use anchor_lang::{AccountDeserialize, AccountSerialize, Space};
+use dao_voting::{GlobalState, UserVault, Vault};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+/// Asserts the account at `address` is allocated `8 + T::INIT_SPACE`
+/// bytes and that `value` serializes to exactly that.
+async fn assert_fits<T: AccountSerialize + AccountDeserialize + Space>(
+    env: &mut TestEnv,
+    address: &Pubkey,
+    value: &T,
+) {
+    let allocated = env
+        .context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+        .len();
+    assert_eq!(allocated, 8 + T::INIT_SPACE);
+
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), allocated);
+}
+
+#[tokio::test]
+async fn allocated_space_fits_largest_accounts() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let vault_id = env.create_vault(VaultParams::new(env.now)).await;
+    env.fund_st_governance(&user, 100).await;
+    env.vote(&user, vault_id, 40).await.unwrap();
+
+    let global_state_address = global_state();
+    let global_state: GlobalState = env.account(&global_state_address).await;
+    assert_fits(&mut env, &global_state_address, &global_state).await;
+
+    // Optional fields serialize at full width only when set
+    let vault_address = vault_address(vault_id);
+    let mut vault: Vault = env.account(&vault_address).await;
+    vault.max_per_user = Some(u128::MAX);
+    vault.min_per_vote = Some(u64::MAX);
+    vault.merkle_root = Some([u8::MAX; 32]);
+    assert_fits(&mut env, &vault_address, &vault).await;
+
+    let user_vault_address = user_vault_address(vault_id, &user.pubkey());
+    let user_vault: UserVault = env.account(&user_vault_address).await;
+    assert_fits(&mut env, &user_vault_address, &user_vault).await;
+}
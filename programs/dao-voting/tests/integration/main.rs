@@ -0,0 +1,12 @@
+//! Integration tests running the program natively against a local bank.
+
+mod claims;
+mod common;
+mod conversion;
+mod governance;
+mod invariants;
+mod layout;
+mod migration;
+mod overflow;
+mod staking;
+mod vaults;
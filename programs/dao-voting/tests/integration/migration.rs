@@ -0,0 +1,269 @@
+//! Accounts written under the original layouts, from before accounts
+//! carried a version, rebuilt byte by byte.
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use anchor_lang::{Discriminator, Space};
+use dao_voting::{
+    ConversionConfig, ConversionLimits, ErrorCode, GlobalState, UserVault, Vault, VaultMode,
+    GLOBAL_STATE_VERSION, USER_VAULT_VERSION, VAULT_VERSION,
+};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+/// `state` in the original layout: the admin and the two mints.
+fn original_global_state(state: &GlobalState) -> Vec<u8> {
+    let mut data = GlobalState::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(state.admin.as_ref());
+    data.extend_from_slice(state.governance_token_mint.as_ref());
+    data.extend_from_slice(state.st_governance_token_mint.as_ref());
+    data
+}
+
+/// `vault` in the original layout, which ended at `st_governance_token_mint`.
+fn original_vault(vault: &Vault) -> Vec<u8> {
+    let mut data = Vault::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(vault.owner.as_ref());
+    data.extend_from_slice(vault.governance_token_mint.as_ref());
+    data.extend_from_slice(vault.vote_token_mint.as_ref());
+    data.extend_from_slice(vault.project_token_mint.as_ref());
+    data.extend_from_slice(&vault.convert_time.to_le_bytes());
+    data.extend_from_slice(&vault.total_burned.to_le_bytes());
+    data.extend_from_slice(&vault.total_deposited.to_le_bytes());
+    data.extend_from_slice(&vault.vault_id.to_le_bytes());
+    data.extend_from_slice(&vault.max_vote_cap.to_le_bytes());
+    data.extend_from_slice(&vault.deadline.to_le_bytes());
+    data.extend_from_slice(vault.st_governance_token_mint.as_ref());
+    data
+}
+
+/// Replaces the data of the program account at `address` with `data`,
+/// holding just the rent for its length.
+async fn write_raw(env: &mut TestEnv, address: &Pubkey, data: Vec<u8>) {
+    let account = env
+        .context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let account = Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        ..account
+    };
+    env.context
+        .set_account(address, &AccountSharedData::from(account));
+}
+
+async fn account_len(env: &mut TestEnv, address: &Pubkey) -> usize {
+    env.context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+        .len()
+}
+
+#[tokio::test]
+async fn migrates_accounts_from_the_original_layouts() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let params = VaultParams::new(env.now);
+    let deadline = params.deadline;
+    let vault_id = env.create_vault(params).await;
+    env.fund_st_governance(&user, 100).await;
+    env.vote(&user, vault_id, 40).await.unwrap();
+
+    let vault = vault_address(vault_id);
+    let state: GlobalState = env.account(&global_state()).await;
+    let current: Vault = env.account(&vault).await;
+    let data = original_global_state(&state);
+    assert_eq!(data.len(), 8 + 96);
+    write_raw(&mut env, &global_state(), data).await;
+    let data = original_vault(&current);
+    assert_eq!(data.len(), 8 + 232);
+    write_raw(&mut env, &vault, data).await;
+    assert_custom_error(
+        env.vote(&user, vault_id, 10).await,
+        AnchorErrorCode::AccountDidNotDeserialize.into(),
+    );
+
+    // Anyone can pay for the migration
+    let payer = env.new_user().await;
+    let ix = migrate_global_state_ix(&payer.pubkey());
+    env.send(&[ix], &[&payer]).await.unwrap();
+    let ix = migrate_vault_ix(&payer.pubkey(), vault_id);
+    env.send(&[ix], &[&payer]).await.unwrap();
+
+    let admin = env.admin.pubkey();
+    let migrated: GlobalState = env.account(&global_state()).await;
+    assert_eq!(migrated.version, GLOBAL_STATE_VERSION);
+    assert_eq!(
+        (
+            migrated.admin,
+            migrated.governance_token_mint,
+            migrated.st_governance_token_mint
+        ),
+        (admin, governance_mint(), st_governance_mint())
+    );
+    assert!(!migrated.paused);
+    // Conversion settings start from the `initialize` defaults
+    assert_eq!(
+        migrated.conversion_config,
+        ConversionConfig::one_to_one(admin)
+    );
+    assert_eq!(migrated.conversion_limits, ConversionLimits::UNLIMITED);
+
+    let migrated: Vault = env.account(&vault).await;
+    assert_eq!(migrated.version, VAULT_VERSION);
+    assert_eq!(
+        (migrated.vault_id, migrated.total_burned, migrated.deadline),
+        (vault_id, 40, deadline)
+    );
+    assert_eq!(migrated.vote_token_mint, current.vote_token_mint);
+    assert_eq!(migrated.max_vote_cap, current.max_vote_cap);
+    assert_eq!(migrated.mode, VaultMode::Burn);
+    assert_eq!((migrated.max_per_user, migrated.merkle_root), (None, None));
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    for (address, space) in [
+        (global_state(), 8 + GlobalState::INIT_SPACE),
+        (vault, 8 + Vault::INIT_SPACE),
+    ] {
+        let account = env
+            .context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.data.len(), space);
+        assert!(rent.is_exempt(account.lamports, space));
+    }
+
+    // The migrated accounts convert, vote and claim
+    let convertor = env.new_user().await;
+    env.fund_governance(&convertor.pubkey(), 50).await;
+    let ix = convert_ix(&convertor.pubkey(), 50, None);
+    env.send(&[ix], &[&convertor]).await.unwrap();
+    let st_account = ata(&convertor.pubkey(), &st_governance_mint());
+    assert_eq!(env.token_balance(&st_account).await, 50);
+
+    env.vote(&user, vault_id, 10).await.unwrap();
+    assert_eq!(env.account::<Vault>(&vault).await.total_burned, 50);
+
+    let project_mint = env.fund_vault(vault_id, deadline, 100).await;
+    env.warp_to(deadline);
+    let ix = claim_ix(&user.pubkey(), vault_id, &project_mint);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let project_account = ata(&user.pubkey(), &project_mint);
+    assert_eq!(env.token_balance(&project_account).await, 100);
+
+    let ix = migrate_global_state_ix(&payer.pubkey());
+    assert_error(env.send(&[ix], &[&payer]).await, ErrorCode::AlreadyMigrated);
+    let ix = migrate_vault_ix(&payer.pubkey(), vault_id);
+    assert_error(env.send(&[ix], &[&payer]).await, ErrorCode::AlreadyMigrated);
+}
+
+#[tokio::test]
+async fn keeps_configured_conversion_settings_when_migrating() {
+    let mut env = TestEnv::new().await;
+    let treasury = env.new_user().await.pubkey();
+    let config = ConversionConfig {
+        rate_numerator: 3,
+        rate_denominator: 2,
+        fee_bps: 100,
+        treasury,
+    };
+    let limits = ConversionLimits {
+        window: 60,
+        global_cap: 1_000,
+        per_user_cap: 10,
+    };
+    env.set_conversion_config(config).await;
+    env.set_conversion_limits(limits).await;
+
+    // An unversioned account that already had conversion settings
+    let mut data = env
+        .context
+        .banks_client
+        .get_account(global_state())
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    let version_offset = 8 + GlobalState::INIT_SPACE - 128 - 1;
+    data[version_offset] = 0;
+    write_raw(&mut env, &global_state(), data).await;
+
+    let payer = env.new_user().await;
+    let ix = migrate_global_state_ix(&payer.pubkey());
+    env.send(&[ix], &[&payer]).await.unwrap();
+    let migrated: GlobalState = env.account(&global_state()).await;
+    assert_eq!(migrated.version, GLOBAL_STATE_VERSION);
+    assert_eq!(
+        (migrated.conversion_config, migrated.conversion_limits),
+        (config, limits)
+    );
+}
+
+#[tokio::test]
+async fn rejects_migrating_missing_accounts() {
+    let mut env = TestEnv::new().await;
+    let payer = env.new_user().await;
+    let ix = migrate_vault_ix(&payer.pubkey(), 0);
+    assert_error(
+        env.send(&[ix], &[&payer]).await,
+        ErrorCode::InvalidMigration,
+    );
+}
+
+#[tokio::test]
+async fn migrates_user_vaults_from_the_original_layout() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let vault_id = env.create_vault(VaultParams::new(env.now)).await;
+    env.fund_st_governance(&user, 100).await;
+    env.vote(&user, vault_id, 40).await.unwrap();
+
+    // The original layout: user, vault and burned_amount, nothing else
+    let vault = vault_address(vault_id);
+    let address = user_vault_address(vault_id, &user.pubkey());
+    let mut data = UserVault::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(user.pubkey().as_ref());
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(&40u128.to_le_bytes());
+    write_raw(&mut env, &address, data).await;
+    assert_custom_error(
+        env.vote(&user, vault_id, 10).await,
+        AnchorErrorCode::AccountDidNotDeserialize.into(),
+    );
+
+    let payer = env.new_user().await;
+    let ix = migrate_user_vault_ix(&payer.pubkey(), vault_id, &user.pubkey());
+    env.send(&[ix], &[&payer]).await.unwrap();
+    assert_eq!(
+        account_len(&mut env, &address).await,
+        8 + UserVault::INIT_SPACE
+    );
+    let user_vault: UserVault = env.account(&address).await;
+    assert_eq!(user_vault.version, USER_VAULT_VERSION);
+    assert_eq!((user_vault.user, user_vault.vault), (user.pubkey(), vault));
+    assert_eq!(
+        (user_vault.burned_amount, user_vault.locked_amount),
+        (40, 0)
+    );
+    assert!(!user_vault.ve_voted);
+
+    env.vote(&user, vault_id, 10).await.unwrap();
+    let user_vault: UserVault = env.account(&address).await;
+    assert_eq!(user_vault.burned_amount, 50);
+
+    let ix = migrate_user_vault_ix(&payer.pubkey(), vault_id, &user.pubkey());
+    assert_error(env.send(&[ix], &[&payer]).await, ErrorCode::AlreadyMigrated);
+}
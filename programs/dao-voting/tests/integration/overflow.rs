@@ -0,0 +1,82 @@
+//! Counter and total overflows, reached by writing near-limit values
+//! straight into program accounts.
+
+use dao_voting::{pda, ErrorCode, GlobalState, GovernanceAction, StakePosition, Vault};
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+async fn update_global_state(env: &mut TestEnv, update: impl FnOnce(&mut GlobalState)) {
+    let mut state: GlobalState = env.account(&global_state()).await;
+    update(&mut state);
+    env.set_program_account(&global_state(), &state).await;
+}
+
+#[tokio::test]
+async fn rejects_counter_overflows() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    update_global_state(&mut env, |state| {
+        state.vault_count = u64::MAX;
+        state.queued_action_count = u64::MAX;
+        state.proposal_count = u64::MAX;
+    })
+    .await;
+
+    let ix = create_vault_ix(&admin, None, u64::MAX, VaultParams::new(env.now));
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::VaultCountOverflow);
+    let action = GovernanceAction::SetPaused { paused: true };
+    let ix = queue_action_ix(&admin, u64::MAX, None, action.clone());
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::QueuedActionCountOverflow,
+    );
+    let ix = create_proposal_ix(&admin, u64::MAX, action);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::ProposalCountOverflow,
+    );
+}
+
+#[tokio::test]
+async fn rejects_vault_total_overflows() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let vault_id = env
+        .create_vault(VaultParams {
+            max_vote_cap: u128::MAX,
+            ..VaultParams::new(env.now)
+        })
+        .await;
+    let project_mint = env.fund_vault(vault_id, env.now + 8 * DAY, 100).await;
+    env.fund_st_governance(&user, 10).await;
+
+    let address = vault_address(vault_id);
+    let mut vault: Vault = env.account(&address).await;
+    vault.total_burned = u128::MAX;
+    vault.total_deposited = u128::MAX;
+    env.set_program_account(&address, &vault).await;
+
+    assert_error(env.vote(&user, vault_id, 1).await, ErrorCode::VoteOverflow);
+    let admin = env.admin.pubkey();
+    env.mint_to(&project_mint, &admin, 1).await;
+    let ix = deposit_ix(&admin, None, vault_id, &project_mint, 1);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::DepositOverflow);
+}
+
+#[tokio::test]
+async fn rejects_stake_overflows() {
+    let mut env = TestEnv::new().await;
+    let staker = env.new_user().await;
+    env.fund_st_governance(&staker, 10).await;
+    let ix = stake_ix(&staker.pubkey(), 1);
+    env.send(&[ix], &[&staker]).await.unwrap();
+
+    let address = pda::find_stake_position_address(&staker.pubkey()).0;
+    let mut position: StakePosition = env.account(&address).await;
+    position.amount = u64::MAX;
+    env.set_program_account(&address, &position).await;
+
+    let ix = stake_ix(&staker.pubkey(), 1);
+    assert_error(env.send(&[ix], &[&staker]).await, ErrorCode::RewardOverflow);
+}
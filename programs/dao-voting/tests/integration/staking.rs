@@ -0,0 +1,230 @@
+use dao_voting::{
+    pda, ErrorCode, GlobalState, RoleKind, StakePosition, UserVault, Vault, VeLock,
+    MAX_VE_LOCK_DURATION,
+};
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+#[tokio::test]
+async fn streams_rewards_to_stakers() {
+    let mut env = TestEnv::new().await;
+    let treasurer = env.new_user().await;
+    let staker = env.new_user().await;
+    env.fund_governance(&treasurer.pubkey(), 1_000_000).await;
+    let st_account = env.fund_st_governance(&staker, 1_000).await;
+
+    let ix = stake_ix(&staker.pubkey(), 1_000);
+    env.send(&[ix], &[&staker]).await.unwrap();
+    let position: StakePosition = env
+        .account(&pda::find_stake_position_address(&staker.pubkey()).0)
+        .await;
+    assert_eq!((position.owner, position.amount), (staker.pubkey(), 1_000));
+    assert_eq!(env.token_balance(&st_account).await, 0);
+    assert_eq!(
+        env.account::<GlobalState>(&global_state())
+            .await
+            .total_staked,
+        1_000
+    );
+
+    let ix = fund_rewards_ix(&treasurer.pubkey(), None, 864_000, DAY);
+    assert_error(
+        env.send(&[ix], &[&treasurer]).await,
+        ErrorCode::Unauthorized,
+    );
+
+    env.grant_role(RoleKind::Treasurer, &treasurer.pubkey())
+        .await;
+    let role = Some(role_address(RoleKind::Treasurer, &treasurer.pubkey()));
+    let ix = fund_rewards_ix(&treasurer.pubkey(), role, 864_000, 0);
+    assert_error(
+        env.send(&[ix], &[&treasurer]).await,
+        ErrorCode::InvalidRewardFunding,
+    );
+    // Rounds down to a zero rate
+    let ix = fund_rewards_ix(&treasurer.pubkey(), role, 10, 100);
+    assert_error(
+        env.send(&[ix], &[&treasurer]).await,
+        ErrorCode::InvalidRewardFunding,
+    );
+
+    let ix = fund_rewards_ix(&treasurer.pubkey(), role, 864_000, DAY);
+    env.send(&[ix], &[&treasurer]).await.unwrap();
+    let state: GlobalState = env.account(&global_state()).await;
+    assert_eq!(state.reward_rate, 10);
+    assert_eq!(state.reward_period_end, env.now + DAY);
+
+    env.advance(DAY / 2);
+    let ix = claim_staking_rewards_ix(&staker.pubkey());
+    env.send(&[ix], &[&staker]).await.unwrap();
+    let governance_account = ata(&staker.pubkey(), &governance_mint());
+    assert_eq!(env.token_balance(&governance_account).await, 432_000);
+
+    let ix = unstake_ix(&staker.pubkey(), 1_001);
+    assert_error(
+        env.send(&[ix], &[&staker]).await,
+        ErrorCode::InsufficientStake,
+    );
+    let ix = unstake_ix(&staker.pubkey(), 1_000);
+    env.send(&[ix], &[&staker]).await.unwrap();
+    assert_eq!(env.token_balance(&st_account).await, 1_000);
+    assert_eq!(
+        env.account::<GlobalState>(&global_state())
+            .await
+            .total_staked,
+        0
+    );
+
+    // Rewards stop accruing once the position is empty
+    env.advance(DAY);
+    let ix = claim_staking_rewards_ix(&staker.pubkey());
+    env.send(&[ix], &[&staker]).await.unwrap();
+    assert_eq!(env.token_balance(&governance_account).await, 432_000);
+}
+
+#[tokio::test]
+async fn locks_governance_for_decaying_vote_weight() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let governance_account = env.fund_governance(&user.pubkey(), 1_000).await;
+    let ve_lock_address = pda::find_ve_lock_address(&user.pubkey()).0;
+
+    let ix = create_ve_lock_ix(&user.pubkey(), 0, DAY);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::InvalidVeLock);
+    let ix = create_ve_lock_ix(&user.pubkey(), 1_000, MAX_VE_LOCK_DURATION + 1);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::InvalidVeLock);
+
+    let duration = MAX_VE_LOCK_DURATION / 2;
+    let ix = create_ve_lock_ix(&user.pubkey(), 1_000, duration);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let ve_lock: VeLock = env.account(&ve_lock_address).await;
+    assert_eq!(ve_lock.amount, 1_000);
+    assert_eq!(ve_lock.unlock_time, env.now + duration);
+    assert_eq!(env.token_balance(&governance_account).await, 0);
+    let escrow = ata(&ve_lock_address, &governance_mint());
+    assert_eq!(env.token_balance(&escrow).await, 1_000);
+
+    // Half the maximum duration earns half the weight, without burning
+    let vault_id = env
+        .create_vault(VaultParams {
+            deadline: env.now + duration + DAY,
+            ..VaultParams::new(env.now)
+        })
+        .await;
+    let ix = ve_vote_ix(&user.pubkey(), vault_id);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let user_vault: UserVault = env
+        .account(&user_vault_address(vault_id, &user.pubkey()))
+        .await;
+    assert_eq!((user_vault.ve_weight, user_vault.burned_amount), (500, 0));
+    assert!(user_vault.ve_voted);
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!((vault.total_ve_weight, vault.total_burned), (500, 0));
+    assert_eq!(
+        env.account::<VeLock>(&ve_lock_address).await.weight_used,
+        500
+    );
+    assert_eq!(env.token_balance(&escrow).await, 1_000);
+    // Lock weight carries no claim, so no vote tokens are minted for it
+    assert!(!env.exists(&ata(&user.pubkey(), &vote_mint(vault_id))).await);
+
+    let ix = ve_vote_ix(&user.pubkey(), vault_id);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::VeWeightAlreadyUsed,
+    );
+    let ix = withdraw_ve_lock_ix(&user.pubkey());
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::VeLockNotExpired);
+
+    // An expired lock has no weight left
+    env.warp_to(ve_lock.unlock_time);
+    let second_vault = env.create_vault(VaultParams::new(env.now)).await;
+    let ix = ve_vote_ix(&user.pubkey(), second_vault);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::NoVotingWeight);
+
+    let ix = withdraw_ve_lock_ix(&user.pubkey());
+    env.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(env.token_balance(&governance_account).await, 1_000);
+    assert!(!env.exists(&ve_lock_address).await);
+}
+
+#[tokio::test]
+async fn keeps_lock_weight_out_of_vote_limits() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let voter = env.new_user().await;
+    env.fund_governance(&user.pubkey(), 1_000).await;
+    let ix = create_ve_lock_ix(&user.pubkey(), 1_000, MAX_VE_LOCK_DURATION);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    // More lock weight than the whole cap and the per-user limit allow
+    let vault_id = env
+        .create_vault(VaultParams {
+            max_vote_cap: 150,
+            max_per_user: Some(100),
+            ..VaultParams::new(env.now)
+        })
+        .await;
+    let ix = ve_vote_ix(&user.pubkey(), vault_id);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!((vault.total_ve_weight, vault.total_burned), (1_000, 0));
+
+    // The lock holder can still burn up to the per-user limit on top of
+    // their lock weight, and other burners get the rest of the cap
+    env.fund_st_governance(&user, 101).await;
+    assert_error(
+        env.vote(&user, vault_id, 101).await,
+        ErrorCode::MaxPerUserExceeded,
+    );
+    env.vote(&user, vault_id, 100).await.unwrap();
+    env.fund_st_governance(&voter, 50).await;
+    env.vote(&voter, vault_id, 50).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!((vault.total_ve_weight, vault.total_burned), (1_000, 150));
+}
+
+#[tokio::test]
+async fn spends_lock_weight_once_across_vaults() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let voter = env.new_user().await;
+    env.fund_governance(&user.pubkey(), 1_000).await;
+    let ix = create_ve_lock_ix(&user.pubkey(), 1_000, MAX_VE_LOCK_DURATION);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    let first = env.create_vault(VaultParams::new(env.now)).await;
+    let second = env.create_vault(VaultParams::new(env.now)).await;
+    let ix = ve_vote_ix(&user.pubkey(), first);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    // The lock has nothing left for a second vault, even as time passes
+    let ix = ve_vote_ix(&user.pubkey(), second);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::NoVotingWeight);
+    env.advance(DAY);
+    let ix = ve_vote_ix(&user.pubkey(), second);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::NoVotingWeight);
+    let vault: Vault = env.account(&vault_address(second)).await;
+    assert_eq!(vault.total_ve_weight, 0);
+
+    // Lock weight is tallied apart from the cap, so it can't crowd out
+    // burners, and claims split the deposit between burned votes only
+    let vault: Vault = env.account(&vault_address(first)).await;
+    assert_eq!(vault.total_ve_weight, 1_000);
+    let ix = update_max_vote_cap_ix(&env.admin.pubkey(), first, 99);
+    env.send_admin(&[ix]).await.unwrap();
+    env.fund_st_governance(&voter, 100).await;
+    assert_error(
+        env.vote(&voter, first, 100).await,
+        ErrorCode::MaxVoteCapExceeded,
+    );
+    env.vote(&voter, first, 99).await.unwrap();
+
+    let deadline = vault.deadline;
+    let mint = env.fund_vault(first, deadline, 990).await;
+    env.warp_to(deadline);
+    let ix = claim_ix(&voter.pubkey(), first, &mint);
+    env.send(&[ix], &[&voter]).await.unwrap();
+    assert_eq!(env.token_balance(&ata(&voter.pubkey(), &mint)).await, 990);
+}
@@ -0,0 +1,500 @@
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use dao_voting::quote::{VaultSummary, VoteQuote};
+use dao_voting::{ErrorCode, GlobalState, RoleKind, ScheduleUpdate, UserVault, Vault, VaultMode};
+use solana_sdk::signature::Signer;
+
+use crate::common::*;
+
+#[tokio::test]
+async fn creates_vaults_with_the_operator_role() {
+    let mut env = TestEnv::new().await;
+    let operator = env.new_user().await;
+    let params = VaultParams::new(env.now);
+
+    let ix = create_vault_ix(&operator.pubkey(), None, 0, params.clone());
+    assert_error(env.send(&[ix], &[&operator]).await, ErrorCode::Unauthorized);
+
+    env.grant_role(RoleKind::Operator, &operator.pubkey()).await;
+    let role = Some(role_address(RoleKind::Operator, &operator.pubkey()));
+    let ix = create_vault_ix(
+        &operator.pubkey(),
+        role,
+        0,
+        VaultParams {
+            deadline: params.voting_start,
+            ..params.clone()
+        },
+    );
+    assert_error(
+        env.send(&[ix], &[&operator]).await,
+        ErrorCode::InvalidVotingWindow,
+    );
+    let ix = create_vault_ix(
+        &operator.pubkey(),
+        role,
+        0,
+        VaultParams {
+            max_deadline_extension: -1,
+            ..params.clone()
+        },
+    );
+    assert_error(
+        env.send(&[ix], &[&operator]).await,
+        ErrorCode::InvalidScheduleUpdate,
+    );
+
+    let ix = create_vault_ix(&operator.pubkey(), role, 0, params.clone());
+    env.send(&[ix], &[&operator]).await.unwrap();
+
+    let vault: Vault = env.account(&vault_address(0)).await;
+    assert_eq!(vault.owner, operator.pubkey());
+    assert_eq!(vault.vault_id, 0);
+    assert_eq!(vault.vote_token_mint, vote_mint(0));
+    assert_eq!(vault.governance_token_mint, governance_mint());
+    assert_eq!(vault.st_governance_token_mint, st_governance_mint());
+    assert_eq!(vault.max_vote_cap, params.max_vote_cap);
+    assert_eq!(vault.voting_start, params.voting_start);
+    assert_eq!(vault.deadline, params.deadline);
+    assert_eq!(vault.mode, VaultMode::Burn);
+    assert!(env.exists(&metadata_address(&vote_mint(0))).await);
+    assert_eq!(
+        env.account::<GlobalState>(&global_state())
+            .await
+            .vault_count,
+        1
+    );
+}
+
+#[tokio::test]
+async fn enforces_the_voting_window() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let vault_id = env
+        .create_vault(VaultParams {
+            voting_start: env.now + DAY,
+            deadline: env.now + 2 * DAY,
+            ..VaultParams::new(env.now)
+        })
+        .await;
+    env.fund_st_governance(&user, 100).await;
+
+    assert_error(
+        env.vote(&user, vault_id, 10).await,
+        ErrorCode::VotingNotStarted,
+    );
+
+    env.advance(DAY);
+    env.vote(&user, vault_id, 10).await.unwrap();
+
+    env.advance(DAY);
+    assert_error(env.vote(&user, vault_id, 10).await, ErrorCode::VotingEnded);
+}
+
+#[tokio::test]
+async fn burns_st_governance_for_vote_tokens_within_limits() {
+    let mut env = TestEnv::new().await;
+    let first = env.new_user().await;
+    let second = env.new_user().await;
+    let vault_id = env
+        .create_vault(VaultParams {
+            max_vote_cap: 100,
+            max_per_user: Some(60),
+            min_per_vote: Some(10),
+            ..VaultParams::new(env.now)
+        })
+        .await;
+    let first_st = env.fund_st_governance(&first, 100).await;
+    env.fund_st_governance(&second, 100).await;
+
+    assert_error(
+        env.vote(&first, vault_id, 5).await,
+        ErrorCode::BelowMinPerVote,
+    );
+    env.vote(&first, vault_id, 60).await.unwrap();
+    assert_error(
+        env.vote(&first, vault_id, 10).await,
+        ErrorCode::MaxPerUserExceeded,
+    );
+    assert_error(
+        env.vote(&second, vault_id, 50).await,
+        ErrorCode::MaxVoteCapExceeded,
+    );
+    env.vote(&second, vault_id, 40).await.unwrap();
+
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.total_burned, 100);
+    let user_vault: UserVault = env
+        .account(&user_vault_address(vault_id, &first.pubkey()))
+        .await;
+    assert_eq!(user_vault.user, first.pubkey());
+    assert_eq!(user_vault.vault, vault_address(vault_id));
+    assert_eq!(user_vault.burned_amount, 60);
+    assert_eq!(env.token_balance(&first_st).await, 40);
+    let first_votes = ata(&first.pubkey(), &vote_mint(vault_id));
+    assert_eq!(env.token_balance(&first_votes).await, 60);
+    assert_eq!(env.mint_supply(&vote_mint(vault_id)).await, 100);
+    assert_eq!(env.mint_supply(&st_governance_mint()).await, 100);
+}
+
+#[tokio::test]
+async fn only_burns_st_governance_for_votes() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let vault_id = env.create_vault(VaultParams::new(env.now)).await;
+
+    // A mint the voter controls can't stand in for st_governance
+    let mint = env.create_mint().await;
+    let fake_st = env.mint_to(&mint, &user.pubkey(), 100).await;
+    let mut ix = vote_ix(&user.pubkey(), vault_id, 50, vec![], None);
+    for meta in &mut ix.accounts {
+        if meta.pubkey == st_governance_mint() {
+            meta.pubkey = mint;
+        } else if meta.pubkey == ata(&user.pubkey(), &st_governance_mint()) {
+            meta.pubkey = fake_st;
+        }
+    }
+    assert_custom_error(
+        env.send(&[ix], &[&user]).await,
+        AnchorErrorCode::ConstraintAddress.into(),
+    );
+    assert_eq!(env.token_balance(&fake_st).await, 100);
+    assert_eq!(env.mint_supply(&vote_mint(vault_id)).await, 0);
+}
+
+#[tokio::test]
+async fn enforces_the_allowlist() {
+    let mut env = TestEnv::new().await;
+    let listed = env.new_user().await;
+    let open = env.new_user().await;
+    let outsider = env.new_user().await;
+    let (root, listed_proof, open_proof) = allowlist(
+        allowlist_leaf(&listed.pubkey(), Some(50)),
+        allowlist_leaf(&open.pubkey(), None),
+    );
+    let vault_id = env
+        .create_vault(VaultParams {
+            merkle_root: Some(root),
+            ..VaultParams::new(env.now)
+        })
+        .await;
+    for user in [&listed, &open, &outsider] {
+        env.fund_st_governance(user, 100).await;
+    }
+
+    let ix = vote_ix(
+        &outsider.pubkey(),
+        vault_id,
+        10,
+        listed_proof.clone(),
+        Some(50),
+    );
+    assert_error(
+        env.send(&[ix], &[&outsider]).await,
+        ErrorCode::NotAllowlisted,
+    );
+    let ix = vote_ix(
+        &listed.pubkey(),
+        vault_id,
+        10,
+        listed_proof.clone(),
+        Some(60),
+    );
+    assert_error(env.send(&[ix], &[&listed]).await, ErrorCode::NotAllowlisted);
+
+    let ix = vote_ix(
+        &listed.pubkey(),
+        vault_id,
+        60,
+        listed_proof.clone(),
+        Some(50),
+    );
+    assert_error(
+        env.send(&[ix], &[&listed]).await,
+        ErrorCode::AllocationExceeded,
+    );
+    let ix = vote_ix(&listed.pubkey(), vault_id, 50, listed_proof, Some(50));
+    env.send(&[ix], &[&listed]).await.unwrap();
+
+    // Leaves without an allocation only cap at max_per_user
+    let ix = vote_ix(&open.pubkey(), vault_id, 80, open_proof, None);
+    env.send(&[ix], &[&open]).await.unwrap();
+
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.total_burned, 130);
+}
+
+#[tokio::test]
+async fn updates_the_allowlist_and_window_before_voting() {
+    let mut env = TestEnv::new().await;
+    let owner = env.admin.pubkey();
+    let start = env.now + DAY;
+    let vault_id = env
+        .create_vault(VaultParams {
+            voting_start: start,
+            deadline: start + DAY,
+            ..VaultParams::new(env.now)
+        })
+        .await;
+
+    let ix = set_merkle_root_ix(&owner, vault_id, Some([7; 32]));
+    env.send_admin(&[ix]).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.merkle_root, Some([7; 32]));
+
+    let ix = update_voting_window_ix(&owner, vault_id, start + 2 * DAY, start + 2 * DAY);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::InvalidVotingWindow);
+    let ix = update_voting_window_ix(&owner, vault_id, start + DAY, start + 3 * DAY);
+    env.send_admin(&[ix]).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(
+        (vault.voting_start, vault.deadline),
+        (start + DAY, start + 3 * DAY)
+    );
+
+    // The window can't run past claims opening
+    env.fund_vault(vault_id, start + 4 * DAY, 100).await;
+    let ix = update_voting_window_ix(&owner, vault_id, start + DAY, start + 4 * DAY + 1);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::ConvertTimeBeforeDeadline,
+    );
+    let ix = update_voting_window_ix(&owner, vault_id, start + DAY, start + 4 * DAY);
+    env.send_admin(&[ix]).await.unwrap();
+
+    env.warp_to(start + DAY);
+    let ix = set_merkle_root_ix(&owner, vault_id, None);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::VotingAlreadyStarted);
+    let ix = update_voting_window_ix(&owner, vault_id, start + 2 * DAY, start + 4 * DAY);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::VotingAlreadyStarted);
+}
+
+#[tokio::test]
+async fn extends_and_closes_the_schedule() {
+    let mut env = TestEnv::new().await;
+    let owner = env.admin.pubkey();
+    let params = VaultParams {
+        max_deadline_extension: DAY,
+        ..VaultParams::new(env.now)
+    };
+    let deadline = params.deadline;
+    let vault_id = env.create_vault(params).await;
+    env.fund_vault(vault_id, deadline + DAY, 100).await;
+
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::Extend { seconds: 0 });
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::InvalidScheduleUpdate,
+    );
+    let ix = update_vault_schedule_ix(
+        &owner,
+        vault_id,
+        ScheduleUpdate::Extend { seconds: DAY + 1 },
+    );
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::MaxExtensionExceeded);
+
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::Extend { seconds: DAY });
+    env.send_admin(&[ix]).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.deadline, deadline + DAY);
+    assert_eq!(vault.convert_time, deadline + 2 * DAY);
+    assert_eq!(vault.deadline_extended, DAY);
+
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::Extend { seconds: 1 });
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::MaxExtensionExceeded);
+
+    // Closing early keeps the gap before claims open
+    env.advance(DAY);
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::CloseNow);
+    env.send_admin(&[ix]).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.deadline, env.now);
+    assert_eq!(vault.convert_time, env.now + DAY);
+
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::CloseNow);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::VotingEnded);
+}
+
+#[tokio::test]
+async fn closes_only_open_vaults_with_claims_after_voting() {
+    let mut env = TestEnv::new().await;
+    let owner = env.admin.pubkey();
+    let start = env.now + DAY;
+    let vault_id = env
+        .create_vault(VaultParams {
+            voting_start: start,
+            deadline: start + DAY,
+            ..VaultParams::new(env.now)
+        })
+        .await;
+
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::CloseNow);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::VotingNotStarted);
+
+    // Vaults configured before the ordering was enforced can have claims
+    // open before the deadline; closing must not carry that forward
+    env.warp_to(start);
+    let address = vault_address(vault_id);
+    let mut vault: Vault = env.account(&address).await;
+    vault.convert_time = start + DAY - 1;
+    env.set_program_account(&address, &vault).await;
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::CloseNow);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::ConvertTimeBeforeDeadline,
+    );
+
+    vault.convert_time = start + 2 * DAY;
+    env.set_program_account(&address, &vault).await;
+    let ix = update_vault_schedule_ix(&owner, vault_id, ScheduleUpdate::CloseNow);
+    env.send_admin(&[ix]).await.unwrap();
+    let vault: Vault = env.account(&address).await;
+    assert_eq!((vault.deadline, vault.convert_time), (start, start + DAY));
+}
+
+#[tokio::test]
+async fn updates_the_max_vote_cap_while_voting() {
+    let mut env = TestEnv::new().await;
+    let owner = env.admin.pubkey();
+    let user = env.new_user().await;
+    let params = VaultParams {
+        max_vote_cap: 100,
+        ..VaultParams::new(env.now)
+    };
+    let deadline = params.deadline;
+    let vault_id = env.create_vault(params).await;
+    env.fund_st_governance(&user, 100).await;
+    env.vote(&user, vault_id, 80).await.unwrap();
+
+    let ix = update_max_vote_cap_ix(&owner, vault_id, 79);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::MaxVoteCapBelowBurned,
+    );
+    let ix = update_max_vote_cap_ix(&owner, vault_id, 200);
+    env.send_admin(&[ix]).await.unwrap();
+    let vault: Vault = env.account(&vault_address(vault_id)).await;
+    assert_eq!(vault.max_vote_cap, 200);
+
+    env.warp_to(deadline);
+    let ix = update_max_vote_cap_ix(&owner, vault_id, 300);
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::VotingEnded);
+}
+
+#[tokio::test]
+async fn locks_votes_until_the_deadline() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let params = VaultParams {
+        mode: VaultMode::Lock,
+        ..VaultParams::new(env.now)
+    };
+    let deadline = params.deadline;
+    let lock_vault = env.create_vault(params).await;
+    let burn_vault = env.create_vault(VaultParams::new(env.now)).await;
+    let st_account = env.fund_st_governance(&user, 100).await;
+
+    assert_error(
+        env.vote(&user, lock_vault, 10).await,
+        ErrorCode::WrongVaultMode,
+    );
+    let ix = lock_vote_ix(&user.pubkey(), burn_vault, 10);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::WrongVaultMode);
+    let ix = set_project_token_ix(
+        &env.admin.pubkey(),
+        None,
+        lock_vault,
+        governance_mint(),
+        deadline,
+    );
+    assert_error(env.send_admin(&[ix]).await, ErrorCode::WrongVaultMode);
+
+    let ix = lock_vote_ix(&user.pubkey(), lock_vault, 70);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let escrow = ata(&vault_address(lock_vault), &st_governance_mint());
+    assert_eq!(env.token_balance(&st_account).await, 30);
+    assert_eq!(env.token_balance(&escrow).await, 70);
+    let vault: Vault = env.account(&vault_address(lock_vault)).await;
+    assert_eq!((vault.total_locked, vault.total_burned), (70, 0));
+
+    // The cap counts locked votes in lock mode
+    let owner = env.admin.pubkey();
+    let ix = update_max_vote_cap_ix(&owner, lock_vault, 69);
+    assert_error(
+        env.send_admin(&[ix]).await,
+        ErrorCode::MaxVoteCapBelowBurned,
+    );
+    let ix = update_max_vote_cap_ix(&owner, lock_vault, 70);
+    env.send_admin(&[ix]).await.unwrap();
+
+    let ix = unlock_vote_ix(&user.pubkey(), lock_vault);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::VotingNotEnded);
+
+    env.warp_to(deadline);
+    let ix = unlock_vote_ix(&user.pubkey(), lock_vault);
+    env.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(env.token_balance(&st_account).await, 100);
+    let user_vault: UserVault = env
+        .account(&user_vault_address(lock_vault, &user.pubkey()))
+        .await;
+    assert_eq!(user_vault.locked_amount, 0);
+    // The vault keeps the final poll weight
+    let vault: Vault = env.account(&vault_address(lock_vault)).await;
+    assert_eq!(vault.total_locked, 70);
+
+    // Locked votes come back through unlock_vote, never through claims
+    env.create_ata(&vault_address(lock_vault), &governance_mint())
+        .await;
+    env.create_ata(&user.pubkey(), &vote_mint(lock_vault)).await;
+    let ix = claim_ix(&user.pubkey(), lock_vault, &governance_mint());
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::WrongVaultMode);
+}
+
+#[tokio::test]
+async fn previews_votes_and_vault_state() {
+    let mut env = TestEnv::new().await;
+    let voter = env.new_user().await;
+    let newcomer = env.new_user().await;
+    let params = VaultParams {
+        max_vote_cap: 100,
+        max_per_user: Some(60),
+        ..VaultParams::new(env.now)
+    };
+    let deadline = params.deadline;
+    let vault_id = env.create_vault(params).await;
+    env.fund_st_governance(&voter, 100).await;
+    env.vote(&voter, vault_id, 20).await.unwrap();
+
+    let quote: VoteQuote = env
+        .view(quote_vote_ix(&voter.pubkey(), vault_id, 30, true))
+        .await
+        .unwrap();
+    assert_eq!(
+        quote,
+        VoteQuote {
+            new_vault_total: 50,
+            new_user_total: 50,
+            remaining_vote_capacity: 50,
+        }
+    );
+    let quote: VoteQuote = env
+        .view(quote_vote_ix(&newcomer.pubkey(), vault_id, 30, false))
+        .await
+        .unwrap();
+    assert_eq!((quote.new_vault_total, quote.new_user_total), (50, 30));
+    let result = env
+        .view::<VoteQuote>(quote_vote_ix(&voter.pubkey(), vault_id, 50, true))
+        .await;
+    assert_eq!(result, Err(custom_error(ErrorCode::MaxPerUserExceeded)));
+
+    let summary: VaultSummary = env.view(vault_summary_ix(vault_id)).await.unwrap();
+    assert_eq!(summary.vault_id, vault_id);
+    assert_eq!(summary.mode, VaultMode::Burn);
+    assert_eq!(summary.vote_total, 20);
+    assert_eq!(summary.remaining_vote_capacity, 80);
+    assert!(summary.voting_open);
+    assert!(!summary.claimable);
+
+    env.warp_to(deadline);
+    let summary: VaultSummary = env.view(vault_summary_ix(vault_id)).await.unwrap();
+    assert!(!summary.voting_open);
+}
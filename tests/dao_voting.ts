@@ -16,11 +16,23 @@ import {
   mintTo,
 } from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 const TOKEN_METADATA_PROGRAM_ID = new anchor.web3.PublicKey(
   "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s",
 );
 
+const sha256 = (...parts: Buffer[]) =>
+  createHash("sha256").update(Buffer.concat(parts)).digest();
+
+const merkleLeaf = (wallet: PublicKey, allocation: anchor.BN | null) =>
+  allocation === null
+    ? sha256(wallet.toBuffer())
+    : sha256(wallet.toBuffer(), allocation.toArrayLike(Buffer, "le", 8));
+
+const hashPair = (a: Buffer, b: Buffer) =>
+  Buffer.compare(a, b) <= 0 ? sha256(a, b) : sha256(b, a);
+
 describe("dao-voting", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
@@ -29,6 +41,7 @@ describe("dao-voting", () => {
   const admin = Keypair.generate();
   const user = Keypair.generate();
   const partnership = Keypair.generate();
+  const operator = Keypair.generate();
 
   let globalState: PublicKey;
   let governanceTokenMint: PublicKey;
@@ -47,11 +60,239 @@ describe("dao-voting", () => {
   let stMetadataAddress: PublicKey;
   let voteTokenMetadataAddress: PublicKey;
 
-  const vaultId = new anchor.BN(1);
+  const vaultId = new anchor.BN(0);
+  const TIMELOCK_DELAY = 2;
+  const PROPOSAL_VOTING_PERIOD = 3;
+  let limitedVaultId: anchor.BN;
+
+  const findVaultAddresses = (id: anchor.BN) => {
+    const [vault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), id.toBuffer("le", 8)],
+      program.programId,
+    );
+    const [voteTokenMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vote_token_mint"), vault.toBuffer()],
+      program.programId,
+    );
+    const [metadata] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("metadata"),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+        voteTokenMint.toBuffer(),
+      ],
+      TOKEN_METADATA_PROGRAM_ID,
+    );
+    const [userVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_vault"), vault.toBuffer(), user.publicKey.toBuffer()],
+      program.programId,
+    );
+    const userVoteTokenAccount = getAssociatedTokenAddressSync(
+      voteTokenMint,
+      user.publicKey,
+    );
+    return { vault, voteTokenMint, metadata, userVault, userVoteTokenAccount };
+  };
+
+  const createVault = async (
+    options: {
+      maxPerUser?: anchor.BN;
+      minPerVote?: anchor.BN;
+      merkleRoot?: number[];
+      votingStart?: anchor.BN;
+      maxDeadlineExtension?: anchor.BN;
+      creator?: Keypair;
+      role?: PublicKey;
+      deadline?: anchor.BN;
+      mode?: object;
+    } = {},
+  ) => {
+    const creator = options.creator ?? admin;
+    const { vaultCount: id } = await program.account.globalState.fetch(
+      globalState,
+    );
+    const addresses = findVaultAddresses(id);
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .createStAAndVault(
+        new anchor.BN(1000000),
+        options.votingStart ?? new anchor.BN(now - 60),
+        options.deadline ?? new anchor.BN(now + 3600),
+        {
+          name: `Vote Token ${id.toString()}`,
+          symbol: "VOTE",
+          uri: "https://example.com/vote-metadata.json",
+        },
+        options.maxPerUser ?? null,
+        options.minPerVote ?? null,
+        options.merkleRoot ?? null,
+        options.maxDeadlineExtension ?? new anchor.BN(0),
+        (options.mode ?? { burn: {} }) as any,
+      )
+      .accounts({
+        vault: addresses.vault,
+        voteTokenMint: addresses.voteTokenMint,
+        globalState,
+        role: options.role ?? null,
+        creator: creator.publicKey,
+        metadata: addresses.metadata,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([creator])
+      .rpc();
+    return id;
+  };
+
+  const voteOn = (
+    id: anchor.BN,
+    amount: number,
+    proof: number[][] = [],
+    allocation: anchor.BN | null = null,
+  ) => {
+    const addresses = findVaultAddresses(id);
+    return program.methods
+      .vote(id, new anchor.BN(amount), proof, allocation)
+      .accounts({
+        globalState,
+        stGovernanceTokenMint,
+        voteTokenMint: addresses.voteTokenMint,
+        userStGovernanceTokenAccount,
+        userVoteTokenAccount: addresses.userVoteTokenAccount,
+        vault: addresses.vault,
+        userVault: addresses.userVault,
+        user: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+  };
+
+  const ROLE_KINDS = [
+    "admin",
+    "operator",
+    "pauser",
+    "treasurer",
+    "projectPartner",
+  ];
+
+  const findRole = (kind: string, holder: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("role"),
+        Buffer.from([ROLE_KINDS.indexOf(kind)]),
+        holder.toBuffer(),
+      ],
+      program.programId,
+    )[0];
+
+  const pause = (pauser: Keypair, role: PublicKey) =>
+    program.methods
+      .pause()
+      .accounts({ globalState, role, pauser: pauser.publicKey })
+      .signers([pauser])
+      .rpc();
+
+  const findQueuedAction = (id: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("queued_action"), id.toBuffer("le", 8)],
+      program.programId,
+    )[0];
+
+  const queueAction = async (
+    action: object,
+    proposer: Keypair = admin,
+    role: PublicKey | null = null,
+  ) => {
+    const { queuedActionCount: id } = await program.account.globalState.fetch(
+      globalState,
+    );
+    await program.methods
+      .queueAction(action as any)
+      .accounts({
+        globalState,
+        role,
+        queuedAction: findQueuedAction(id),
+        proposer: proposer.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([proposer])
+      .rpc();
+    return id;
+  };
+
+  const executeAction = (id: anchor.BN) =>
+    program.methods
+      .executeAction(id)
+      .accounts({
+        globalState,
+        queuedAction: findQueuedAction(id),
+        proposer: admin.publicKey,
+      })
+      .rpc();
+
+  const findConversionWindow = (owner?: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      owner
+        ? [Buffer.from("conversion_window"), owner.toBuffer()]
+        : [Buffer.from("conversion_window")],
+      program.programId,
+    )[0];
+
+  const waitForTimelock = () =>
+    new Promise((resolve) => setTimeout(resolve, (TIMELOCK_DELAY + 1) * 1000));
+
+  // Role changes are queued like any other admin action and executed by
+  // grantRole/revokeRole once the timelock has passed.
+  const grantRole = async (
+    kind: string,
+    holder: PublicKey,
+    proposer: Keypair = admin,
+    proposerRole: PublicKey | null = null,
+  ) => {
+    const id = await queueAction(
+      { grantRole: { kind: { [kind]: {} }, holder } },
+      proposer,
+      proposerRole,
+    );
+    await waitForTimelock();
+    const role = findRole(kind, holder);
+    await program.methods
+      .grantRole(id, { [kind]: {} } as any)
+      .accounts({
+        queuedAction: findQueuedAction(id),
+        proposer: proposer.publicKey,
+        role,
+        holder,
+        payer: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+    return role;
+  };
+
+  const revokeRole = async (role: PublicKey) => {
+    const { kind, holder, grantedBy } = await program.account.role.fetch(role);
+    const id = await queueAction({ revokeRole: { kind, holder } });
+    await waitForTimelock();
+    return program.methods
+      .revokeRole(id)
+      .accounts({
+        queuedAction: findQueuedAction(id),
+        proposer: admin.publicKey,
+        role,
+        grantedBy,
+      })
+      .rpc();
+  };
 
   before(async () => {
-    // Airdrop SOL to admin, user, and partnership
-    for (const account of [admin, user, partnership]) {
+    // Airdrop SOL to admin, user, partnership, and operator
+    for (const account of [admin, user, partnership, operator]) {
       const signature = await provider.connection.requestAirdrop(
         account.publicKey,
         10 * anchor.web3.LAMPORTS_PER_SOL,
@@ -119,7 +360,11 @@ describe("dao-voting", () => {
 
     try {
       await program.methods
-        .initialize(metadata, stMetadata)
+        .initialize(metadata, stMetadata, new anchor.BN(TIMELOCK_DELAY), {
+          votingPeriod: new anchor.BN(PROPOSAL_VOTING_PERIOD),
+          quorum: new anchor.BN(100),
+          thresholdBps: 5000,
+        })
         .accounts({
           globalState,
           governanceTokenMint,
@@ -147,6 +392,10 @@ describe("dao-voting", () => {
       expect(globalStateAccount.stGovernanceTokenMint.toString()).to.equal(
         stGovernanceTokenMint.toString(),
       );
+      expect(globalStateAccount.vaultCount.toString()).to.equal("0");
+      expect(globalStateAccount.timelockDelay.toString()).to.equal(
+        TIMELOCK_DELAY.toString(),
+      );
     } catch (error) {
       console.error("Error in initialize:", error);
       throw error;
@@ -192,10 +441,13 @@ describe("dao-voting", () => {
           userStGovernanceTokenAccount,
           user: user.publicKey,
           globalState,
+          globalConversionWindow: findConversionWindow(),
+          userConversionWindow: findConversionWindow(user.publicKey),
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           rent: SYSVAR_RENT_PUBKEY,
+          treasuryGovernanceTokenAccount: null,
         })
         .signers([user])
         .rpc();
@@ -219,6 +471,7 @@ describe("dao-voting", () => {
 
   it("Creates stA and vault", async () => {
     const maxVoteCap = new anchor.BN(1000000); // Example max vote cap
+    const votingStart = new anchor.BN(Math.floor(Date.now() / 1000) - 60);
     const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 10); // 1 hour from now
     const metadata = {
       name: "Vote Token",
@@ -237,12 +490,23 @@ describe("dao-voting", () => {
 
     try {
       await program.methods
-        .createStAAndVault(vaultId, maxVoteCap, deadline, metadata)
+        .createStAAndVault(
+          maxVoteCap,
+          votingStart,
+          deadline,
+          metadata,
+          null,
+          null,
+          null,
+          new anchor.BN(0),
+          { burn: {} } as any,
+        )
         .accounts({
           vault,
           voteTokenMint,
           globalState,
-          admin: admin.publicKey,
+          role: null,
+          creator: admin.publicKey,
           metadata: voteTokenMetadataAddress,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -268,7 +532,15 @@ describe("dao-voting", () => {
       voteTokenMint.toString(),
     );
     expect(vaultAccount.vaultId.toString()).to.equal(vaultId.toString());
+
+    const globalStateAccount = await program.account.globalState.fetch(
+      globalState,
+    );
+    expect(globalStateAccount.vaultCount.toString()).to.equal("1");
     expect(vaultAccount.maxVoteCap.toString()).to.equal(maxVoteCap.toString());
+    expect(vaultAccount.votingStart.toString()).to.equal(
+      votingStart.toString(),
+    );
     expect(vaultAccount.deadline.toString()).to.equal(deadline.toString());
   });
 
@@ -289,8 +561,9 @@ describe("dao-voting", () => {
 
     try {
       await program.methods
-        .vote(vaultId, amount)
+        .vote(vaultId, amount, [], null)
         .accounts({
+          globalState,
           stGovernanceTokenMint,
           voteTokenMint,
           userStGovernanceTokenAccount,
@@ -322,123 +595,1168 @@ describe("dao-voting", () => {
     }
   });
 
-  it("Fails to vote after deadline", async () => {
-    // Wait for the deadline to pass
-    await new Promise((resolve) => setTimeout(resolve, 10 * 1000));
+  it("Enforces per-user vote limits", async () => {
+    limitedVaultId = await createVault({
+      maxPerUser: new anchor.BN(60),
+      minPerVote: new anchor.BN(20),
+    });
+    const limited = findVaultAddresses(limitedVaultId);
 
-    const amount = new anchor.BN(50);
+    const vaultAccount = await program.account.vault.fetch(limited.vault);
+    expect(vaultAccount.maxPerUser.toString()).to.equal("60");
+    expect(vaultAccount.minPerVote.toString()).to.equal("20");
 
     try {
-      await program.methods
-        .vote(vaultId, amount)
+      await voteOn(limitedVaultId, 10);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include(
+        "Vote amount is below the vault minimum",
+      );
+    }
+
+    await voteOn(limitedVaultId, 40);
+
+    try {
+      await voteOn(limitedVaultId, 30);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Max per-user vote cap exceeded");
+    }
+
+    const userVaultAccount = await program.account.userVault.fetch(
+      limited.userVault,
+    );
+    expect(userVaultAccount.burnedAmount.toString()).to.equal("40");
+  });
+
+  it("Previews votes and vault state through simulation", async () => {
+    const limited = findVaultAddresses(limitedVaultId);
+    const quoteVote = (amount: number, userVault: PublicKey | null) =>
+      program.methods
+        .quoteVote(limitedVaultId, new anchor.BN(amount), [], null)
         .accounts({
-          stGovernanceTokenMint,
-          voteTokenMint,
-          userStGovernanceTokenAccount,
-          userVoteTokenAccount,
-          vault,
+          globalState,
+          vault: limited.vault,
           userVault,
           user: user.publicKey,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         })
-        .signers([user])
-        .rpc();
+        .view();
 
-      // If we reach here, the test should fail
+    const voteQuote = await quoteVote(20, limited.userVault);
+    expect(voteQuote.newUserTotal.toString()).to.equal("60");
+    expect(voteQuote.newVaultTotal.toString()).to.equal("60");
+    expect(voteQuote.remainingVoteCapacity.toString()).to.equal("999940");
+
+    try {
+      await quoteVote(30, limited.userVault);
       expect.fail("Expected an error, but none was thrown");
     } catch (error) {
-      expect(error.message).to.include("Voting period has ended");
+      expect(error.message).to.include("Max per-user vote cap exceeded");
     }
+
+    // A user without a record yet previews from zero
+    const firstVoteQuote = await quoteVote(60, null);
+    expect(firstVoteQuote.newUserTotal.toString()).to.equal("60");
+
+    const summary = await program.methods
+      .vaultSummary(limitedVaultId)
+      .accounts({ vault: limited.vault })
+      .view();
+    expect(summary.voteTotal.toString()).to.equal("40");
+    expect(summary.votingOpen).to.be.true;
+    expect(summary.claimable).to.be.false;
   });
 
-  it("Sets project token", async () => {
-    projectTokenMint = await createMint(
-      provider.connection,
-      admin,
-      admin.publicKey,
-      null,
-      6,
-    );
+  it("Restricts voting to allowlisted wallets", async () => {
+    const allocation = new anchor.BN(30);
 
-    const convertTime = Math.floor(Date.now() / 1000) + 10; // 30 seconds from now
+    const userLeaf = merkleLeaf(user.publicKey, allocation);
+    const otherLeaf = merkleLeaf(Keypair.generate().publicKey, null);
+    const root = hashPair(userLeaf, otherLeaf);
+
+    const allowlistVaultId = await createVault({
+      merkleRoot: Array.from(root),
+    });
+    const allowlisted = findVaultAddresses(allowlistVaultId);
+
+    try {
+      await voteOn(allowlistVaultId, 10, [Array.from(otherLeaf)], null);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("User is not on the vault allowlist");
+    }
+
+    await voteOn(allowlistVaultId, 20, [Array.from(otherLeaf)], allocation);
+
+    try {
+      await voteOn(allowlistVaultId, 20, [Array.from(otherLeaf)], allocation);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Allowlist allocation exceeded");
+    }
+
+    try {
+      await program.methods
+        .setMerkleRoot(null)
+        .accounts({
+          globalState,
+          role: null,
+          vault: allowlisted.vault,
+          owner: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Voting has already started");
+    }
+  });
+
+  it("Opens voting at the configured start time", async () => {
+    const now = Math.floor(Date.now() / 1000);
+    const scheduledVaultId = await createVault({
+      votingStart: new anchor.BN(now + 1800),
+    });
+    const scheduled = findVaultAddresses(scheduledVaultId);
+
+    try {
+      await voteOn(scheduledVaultId, 10);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Voting has not started yet");
+    }
+
+    try {
+      await program.methods
+        .updateVotingWindow(new anchor.BN(now + 600), new anchor.BN(now + 300))
+        .accounts({
+          globalState,
+          role: null,
+          vault: scheduled.vault,
+          owner: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include(
+        "Voting start must be before the deadline",
+      );
+    }
 
     await program.methods
-      .setProjectToken(projectTokenMint, new anchor.BN(convertTime))
+      .updateVotingWindow(new anchor.BN(now - 60), new anchor.BN(now + 600))
       .accounts({
-        vault,
+        globalState,
+        role: null,
+        vault: scheduled.vault,
         owner: admin.publicKey,
       })
       .signers([admin])
       .rpc();
 
-    const vaultAccount = await program.account.vault.fetch(vault);
-    expect(vaultAccount.projectTokenMint.toString()).to.equal(
-      projectTokenMint.toString(),
-    );
-    expect(vaultAccount.convertTime.toString()).to.equal(
-      convertTime.toString(),
-    );
+    const vaultAccount = await program.account.vault.fetch(scheduled.vault);
+    expect(vaultAccount.votingStart.toString()).to.equal((now - 60).toString());
+    expect(vaultAccount.deadline.toString()).to.equal((now + 600).toString());
+
+    await voteOn(scheduledVaultId, 10);
   });
 
-  it("Deposits project tokens", async () => {
-    const amount = new anchor.BN(1000);
+  it("Extends and closes the voting schedule", async () => {
+    const scheduleVaultId = await createVault({
+      maxDeadlineExtension: new anchor.BN(600),
+    });
+    const schedule = findVaultAddresses(scheduleVaultId);
+    const { deadline } = await program.account.vault.fetch(schedule.vault);
 
-    // Create partnership project token account and mint some tokens
-    partnershipProjectTokenAccount = await getOrCreateAssociatedTokenAccount(
-      provider.connection,
-      admin,
-      projectTokenMint,
-      partnership.publicKey,
-    ).then((account) => account.address);
+    const updateSchedule = (update: object) =>
+      program.methods
+        .updateVaultSchedule(update as any)
+        .accounts({
+          globalState,
+          role: null,
+          vault: schedule.vault,
+          owner: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
 
-    await mintTo(
-      provider.connection,
-      admin,
-      projectTokenMint,
-      partnershipProjectTokenAccount,
-      admin,
-      2000,
+    await updateSchedule({ extend: { seconds: new anchor.BN(300) } });
+    let vaultAccount = await program.account.vault.fetch(schedule.vault);
+    expect(vaultAccount.deadline.toString()).to.equal(
+      deadline.addn(300).toString(),
     );
+    expect(vaultAccount.deadlineExtended.toString()).to.equal("300");
 
-    vaultTokenAccount = await getAssociatedTokenAddressSync(
-      projectTokenMint,
-      vault,
-      true,
-    );
+    try {
+      await updateSchedule({ extend: { seconds: new anchor.BN(400) } });
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include(
+        "Deadline extension exceeds the vault maximum",
+      );
+    }
 
-    await program.methods
-      .depositProjectTokens(amount)
-      .accounts({
-        vault,
-        projectTokenAccount: partnershipProjectTokenAccount,
-        vaultTokenAccount,
-        projectTokenMint,
-        projectAuthority: partnership.publicKey,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-        rent: SYSVAR_RENT_PUBKEY,
-      })
-      .signers([partnership])
-      .rpc();
+    await updateSchedule({ closeNow: {} });
+    vaultAccount = await program.account.vault.fetch(schedule.vault);
+    expect(vaultAccount.deadline.lt(deadline)).to.be.true;
 
-    const vaultAccount = await program.account.vault.fetch(vault);
-    expect(vaultAccount.totalDeposited.toString()).to.equal("1000");
+    try {
+      await voteOn(scheduleVaultId, 10);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Voting period has ended");
+    }
   });
 
-  it("Claims project tokens", async () => {
-    await new Promise((resolve) => setTimeout(resolve, 10 * 1000)); // Wait for 30 seconds
+  it("Updates the max vote cap while voting is open", async () => {
+    const { vault: limitedVault } = findVaultAddresses(limitedVaultId);
 
-    userProjectTokenAccount = await getAssociatedTokenAddressSync(
-      projectTokenMint,
-      user.publicKey,
-    );
+    try {
+      await program.methods
+        .updateMaxVoteCap(new anchor.BN(39))
+        .accounts({
+          globalState,
+          role: null,
+          vault: limitedVault,
+          owner: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include(
+        "Max vote cap cannot be below the votes already cast",
+      );
+    }
 
     await program.methods
-      .claimProjectTokens(vaultId)
+      .updateMaxVoteCap(new anchor.BN(2000000))
       .accounts({
+        globalState,
+        role: null,
+        vault: limitedVault,
+        owner: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(limitedVault);
+    expect(vaultAccount.maxVoteCap.toString()).to.equal("2000000");
+  });
+
+  it("Lets granted operators create and own vaults", async () => {
+    try {
+      await createVault({ creator: operator });
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+
+    const operatorRole = await grantRole("operator", operator.publicKey);
+
+    const operatorVaultId = await createVault({
+      creator: operator,
+      role: operatorRole,
+    });
+    const operatorVault = findVaultAddresses(operatorVaultId);
+    const vaultAccount = await program.account.vault.fetch(operatorVault.vault);
+    expect(vaultAccount.owner.toString()).to.equal(
+      operator.publicKey.toString(),
+    );
+
+    await revokeRole(operatorRole);
+
+    try {
+      await createVault({ creator: operator, role: operatorRole });
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("AccountNotInitialized");
+    }
+  });
+
+  it("Enforces role boundaries", async () => {
+    // Only admins can queue role changes
+    try {
+      await queueAction(
+        { grantRole: { kind: { pauser: {} }, holder: user.publicKey } },
+        user,
+      );
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+
+    // Delegated admins can queue role changes, but not for the admin role
+    const partnershipAdminRole = await grantRole(
+      "admin",
+      partnership.publicKey,
+    );
+    const pauserRole = await grantRole(
+      "pauser",
+      operator.publicKey,
+      partnership,
+      partnershipAdminRole,
+    );
+    try {
+      await queueAction(
+        { grantRole: { kind: { admin: {} }, holder: user.publicKey } },
+        partnership,
+        partnershipAdminRole,
+      );
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+    await revokeRole(partnershipAdminRole);
+
+    // A role of a different kind does not grant pauser rights
+    const userOperatorRole = await grantRole("operator", user.publicKey);
+    try {
+      await pause(user, userOperatorRole);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+    await revokeRole(userOperatorRole);
+
+    // Pausers can halt voting, unpausing goes through the timelock
+    await pause(operator, pauserRole);
+    try {
+      await voteOn(limitedVaultId, 20);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Program is paused");
+    }
+    const unpauseId = await queueAction({ setPaused: { paused: false } });
+    await waitForTimelock();
+    await executeAction(unpauseId);
+    await revokeRole(pauserRole);
+  });
+
+  it("Applies admin actions through the timelock", async () => {
+    try {
+      await queueAction({ transferAdmin: { newAdmin: user.publicKey } }, user);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+
+    const delayId = await queueAction({
+      setTimelockDelay: { delay: new anchor.BN(TIMELOCK_DELAY + 1) },
+    });
+    try {
+      await executeAction(delayId);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Timelock has not expired");
+    }
+
+    await waitForTimelock();
+    await executeAction(delayId);
+    let globalStateAccount = await program.account.globalState.fetch(
+      globalState,
+    );
+    expect(globalStateAccount.timelockDelay.toString()).to.equal(
+      (TIMELOCK_DELAY + 1).toString(),
+    );
+
+    const transferId = await queueAction({
+      transferAdmin: { newAdmin: user.publicKey },
+    });
+    await program.methods
+      .cancelAction(transferId)
+      .accounts({
+        globalState,
+        role: null,
+        queuedAction: findQueuedAction(transferId),
+        proposer: admin.publicKey,
+        authority: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+    expect(
+      await provider.connection.getAccountInfo(findQueuedAction(transferId)),
+    ).to.be.null;
+
+    const restoreId = await queueAction({
+      setTimelockDelay: { delay: new anchor.BN(TIMELOCK_DELAY) },
+    });
+    await new Promise((resolve) =>
+      setTimeout(resolve, (TIMELOCK_DELAY + 2) * 1000),
+    );
+    await executeAction(restoreId);
+    globalStateAccount = await program.account.globalState.fetch(globalState);
+    expect(globalStateAccount.admin.toString()).to.equal(
+      admin.publicKey.toString(),
+    );
+  });
+
+  it("Converts at the configured rate with a treasury fee", async () => {
+    const treasuryGovernanceTokenAccount =
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        governanceTokenMint,
+        partnership.publicKey,
+      ).then((account) => account.address);
+    const convertAccounts = {
+      governanceTokenMint,
+      stGovernanceTokenMint,
+      userGovernanceTokenAccount,
+      userStGovernanceTokenAccount,
+      user: user.publicKey,
+      globalState,
+      globalConversionWindow: findConversionWindow(),
+      userConversionWindow: findConversionWindow(user.publicKey),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      rent: SYSVAR_RENT_PUBKEY,
+    };
+
+    try {
+      await queueAction({
+        setConversionConfig: {
+          config: {
+            rateNumerator: new anchor.BN(1),
+            rateDenominator: new anchor.BN(1),
+            feeBps: 10_001,
+            treasury: partnership.publicKey,
+          },
+        },
+      });
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Invalid conversion config");
+    }
+
+    const configId = await queueAction({
+      setConversionConfig: {
+        config: {
+          rateNumerator: new anchor.BN(3),
+          rateDenominator: new anchor.BN(2),
+          feeBps: 250,
+          treasury: partnership.publicKey,
+        },
+      },
+    });
+    await waitForTimelock();
+    await executeAction(configId);
+
+    await mintTo(
+      provider.connection,
+      admin,
+      governanceTokenMint,
+      userGovernanceTokenAccount,
+      admin,
+      101,
+    );
+
+    try {
+      await program.methods
+        .convertToStGovernance(new anchor.BN(101))
+        .accounts({ ...convertAccounts, treasuryGovernanceTokenAccount: null })
+        .signers([user])
+        .rpc();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Invalid treasury account");
+    }
+
+    const { amount: stBalanceBefore } = (
+      await provider.connection.getTokenAccountBalance(
+        userStGovernanceTokenAccount,
+      )
+    ).value;
+    await program.methods
+      .convertToStGovernance(new anchor.BN(101))
+      .accounts({ ...convertAccounts, treasuryGovernanceTokenAccount })
+      .signers([user])
+      .rpc();
+
+    // 2.5% of 101 rounds down to a fee of 2; the remaining 99 mint 148
+    const { amount: treasuryBalance } = (
+      await provider.connection.getTokenAccountBalance(
+        treasuryGovernanceTokenAccount,
+      )
+    ).value;
+    expect(treasuryBalance).to.equal("2");
+    const { amount: stBalanceAfter } = (
+      await provider.connection.getTokenAccountBalance(
+        userStGovernanceTokenAccount,
+      )
+    ).value;
+    expect(Number(stBalanceAfter) - Number(stBalanceBefore)).to.equal(148);
+  });
+
+  it("Caps rolling-window conversions globally and per user", async () => {
+    const convert = (owner: Keypair, amount: number) =>
+      program.methods
+        .convertToStGovernance(new anchor.BN(amount))
+        .accounts({
+          governanceTokenMint,
+          stGovernanceTokenMint,
+          userGovernanceTokenAccount: getAssociatedTokenAddressSync(
+            governanceTokenMint,
+            owner.publicKey,
+          ),
+          userStGovernanceTokenAccount: getAssociatedTokenAddressSync(
+            stGovernanceTokenMint,
+            owner.publicKey,
+          ),
+          user: owner.publicKey,
+          globalState,
+          globalConversionWindow: findConversionWindow(),
+          userConversionWindow: findConversionWindow(owner.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+          treasuryGovernanceTokenAccount: getAssociatedTokenAddressSync(
+            governanceTokenMint,
+            partnership.publicKey,
+          ),
+        })
+        .signers([owner])
+        .rpc();
+
+    try {
+      await queueAction({
+        setConversionLimits: {
+          limits: {
+            window: new anchor.BN(0),
+            globalCap: new anchor.BN(0),
+            perUserCap: new anchor.BN(0),
+          },
+        },
+      });
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Invalid conversion limits");
+    }
+
+    // A fresh window length also clears what was converted so far
+    const limitsId = await queueAction({
+      setConversionLimits: {
+        limits: {
+          window: new anchor.BN(60 * 60),
+          globalCap: new anchor.BN(100),
+          perUserCap: new anchor.BN(60),
+        },
+      },
+    });
+    await waitForTimelock();
+    await executeAction(limitsId);
+
+    for (const owner of [user, partnership]) {
+      await mintTo(
+        provider.connection,
+        admin,
+        governanceTokenMint,
+        getAssociatedTokenAddressSync(governanceTokenMint, owner.publicKey),
+        admin,
+        60,
+      );
+    }
+
+    await convert(user, 60);
+    try {
+      await convert(user, 1);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Conversion cap exceeded");
+    }
+
+    try {
+      await convert(partnership, 41);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Conversion cap exceeded");
+    }
+    await convert(partnership, 40);
+
+    const globalWindow = await program.account.conversionWindow.fetch(
+      findConversionWindow(),
+    );
+    expect(globalWindow.converted.toString()).to.equal("100");
+  });
+
+  it("Applies approved proposals to GlobalState", async () => {
+    const { proposalCount: proposalId } =
+      await program.account.globalState.fetch(globalState);
+    const [proposal] = PublicKey.findProgramAddressSync(
+      [Buffer.from("proposal"), proposalId.toBuffer("le", 8)],
+      program.programId,
+    );
+    const [proposalVote] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("proposal_vote"),
+        proposal.toBuffer(),
+        user.publicKey.toBuffer(),
+      ],
+      program.programId,
+    );
+    const proposalTokenAccount = getAssociatedTokenAddressSync(
+      stGovernanceTokenMint,
+      proposal,
+      true,
+    );
+    const newConfig = {
+      votingPeriod: new anchor.BN(PROPOSAL_VOTING_PERIOD),
+      quorum: new anchor.BN(200),
+      thresholdBps: 6000,
+    };
+
+    await program.methods
+      .createProposal({ setProposalConfig: { config: newConfig } } as any)
+      .accounts({
+        globalState,
+        proposal,
+        proposer: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const { amount: balanceBefore } = (
+      await provider.connection.getTokenAccountBalance(
+        userStGovernanceTokenAccount,
+      )
+    ).value;
+
+    await program.methods
+      .castProposalVote(proposalId, { yes: {} } as any, new anchor.BN(150))
+      .accounts({
+        globalState,
+        proposal,
+        proposalVote,
+        stGovernanceTokenMint,
+        voterStGovernanceTokenAccount: userStGovernanceTokenAccount,
+        proposalTokenAccount,
+        voter: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const executeProposal = async () => {
+      const { queuedActionCount } = await program.account.globalState.fetch(
+        globalState,
+      );
+      await program.methods
+        .executeProposal(proposalId)
+        .accounts({
+          globalState,
+          proposal,
+          queuedAction: findQueuedAction(queuedActionCount),
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      return queuedActionCount;
+    };
+
+    try {
+      await executeProposal();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Proposal voting is still active");
+    }
+
+    await new Promise((resolve) =>
+      setTimeout(resolve, (PROPOSAL_VOTING_PERIOD + 1) * 1000),
+    );
+    // A passed proposal waits out the timelock like any queued action
+    const queuedActionId = await executeProposal();
+    let globalStateAccount = await program.account.globalState.fetch(
+      globalState,
+    );
+    expect(globalStateAccount.proposalConfig.quorum.toString()).not.to.equal(
+      "200",
+    );
+    await waitForTimelock();
+    await executeAction(queuedActionId);
+
+    globalStateAccount = await program.account.globalState.fetch(
+      globalState,
+    );
+    expect(globalStateAccount.proposalConfig.quorum.toString()).to.equal("200");
+    expect(globalStateAccount.proposalConfig.thresholdBps).to.equal(6000);
+
+    await program.methods
+      .withdrawProposalVote(proposalId)
+      .accounts({
+        globalState,
+        proposal,
+        proposalVote,
+        stGovernanceTokenMint,
+        voterStGovernanceTokenAccount: userStGovernanceTokenAccount,
+        proposalTokenAccount,
+        voter: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const { amount: balanceAfter } = (
+      await provider.connection.getTokenAccountBalance(
+        userStGovernanceTokenAccount,
+      )
+    ).value;
+    expect(balanceAfter).to.equal(balanceBefore);
+  });
+
+  it("Locks and returns st_governance in lock-mode vaults", async () => {
+    const lockVaultId = await createVault({
+      deadline: new anchor.BN(Math.floor(Date.now() / 1000) + 5),
+      mode: { lock: {} },
+    });
+    const lockVault = findVaultAddresses(lockVaultId);
+    const vaultStGovernanceTokenAccount = getAssociatedTokenAddressSync(
+      stGovernanceTokenMint,
+      lockVault.vault,
+      true,
+    );
+
+    const { amount: balanceBefore } = (
+      await provider.connection.getTokenAccountBalance(
+        userStGovernanceTokenAccount,
+      )
+    ).value;
+
+    try {
+      await voteOn(lockVaultId, 10);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include(
+        "Instruction not supported for this vault mode",
+      );
+    }
+
+    await program.methods
+      .lockVote(lockVaultId, new anchor.BN(50), [], null)
+      .accounts({
+        globalState,
+        vault: lockVault.vault,
+        userVault: lockVault.userVault,
+        stGovernanceTokenMint,
+        userStGovernanceTokenAccount,
+        vaultStGovernanceTokenAccount,
+        user: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const unlockVote = () =>
+      program.methods
+        .unlockVote(lockVaultId)
+        .accounts({
+          vault: lockVault.vault,
+          userVault: lockVault.userVault,
+          stGovernanceTokenMint,
+          userStGovernanceTokenAccount,
+          vaultStGovernanceTokenAccount,
+          user: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+
+    try {
+      await unlockVote();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Voting period has not ended");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 6 * 1000));
+    await unlockVote();
+
+    const vaultAccount = await program.account.vault.fetch(lockVault.vault);
+    expect(vaultAccount.totalLocked.toString()).to.equal("50");
+    const userVaultAccount = await program.account.userVault.fetch(
+      lockVault.userVault,
+    );
+    expect(userVaultAccount.lockedAmount.toString()).to.equal("0");
+
+    const { amount: balanceAfter } = (
+      await provider.connection.getTokenAccountBalance(
+        userStGovernanceTokenAccount,
+      )
+    ).value;
+    expect(balanceAfter).to.equal(balanceBefore);
+  });
+
+  it("Votes with decaying weight from a governance lock", async () => {
+    const maxLockDuration = 4 * 365 * 24 * 60 * 60;
+    const lockAmount = new anchor.BN(400);
+    const [veLock] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ve_lock"), user.publicKey.toBuffer()],
+      program.programId,
+    );
+    const veLockTokenAccount = getAssociatedTokenAddressSync(
+      governanceTokenMint,
+      veLock,
+      true,
+    );
+
+    await program.methods
+      .createVeLock(lockAmount, new anchor.BN(maxLockDuration / 4))
+      .accounts({
+        globalState,
+        veLock,
+        governanceTokenMint,
+        ownerGovernanceTokenAccount: userGovernanceTokenAccount,
+        veLockTokenAccount,
+        owner: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const veVaultId = await createVault();
+    const veVault = findVaultAddresses(veVaultId);
+    const veVote = () =>
+      program.methods
+        .veVote(veVaultId, [], null)
+        .accounts({
+          globalState,
+          vault: veVault.vault,
+          userVault: veVault.userVault,
+          veLock,
+          user: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+    await veVote();
+
+    // A quarter of the max duration is worth a quarter of the locked amount,
+    // less whatever has decayed since the lock was created
+    const userVaultAccount = await program.account.userVault.fetch(
+      veVault.userVault,
+    );
+    expect(userVaultAccount.veWeight.toNumber()).to.be.within(99, 100);
+    expect(userVaultAccount.burnedAmount.toNumber()).to.equal(0);
+    expect(userVaultAccount.veVoted).to.be.true;
+
+    try {
+      await veVote();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Lock weight already used");
+    }
+
+    try {
+      await program.methods
+        .withdrawVeLock()
+        .accounts({
+          globalState,
+          veLock,
+          governanceTokenMint,
+          ownerGovernanceTokenAccount: userGovernanceTokenAccount,
+          veLockTokenAccount,
+          owner: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Lock has not expired");
+    }
+  });
+
+  it("Streams staking rewards to st_governance stakers", async () => {
+    const [stakePosition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake"), user.publicKey.toBuffer()],
+      program.programId,
+    );
+    const stakeTokenAccount = getAssociatedTokenAddressSync(
+      stGovernanceTokenMint,
+      globalState,
+      true,
+    );
+    const rewardTokenAccount = getAssociatedTokenAddressSync(
+      governanceTokenMint,
+      globalState,
+      true,
+    );
+
+    await program.methods
+      .stakeStGovernance(new anchor.BN(100))
+      .accounts({
+        globalState,
+        stakePosition,
+        stGovernanceTokenMint,
+        ownerStGovernanceTokenAccount: userStGovernanceTokenAccount,
+        stakeTokenAccount,
+        owner: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const adminGovernanceTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      admin,
+      governanceTokenMint,
+      admin.publicKey,
+    ).then((account) => account.address);
+    await mintTo(
+      provider.connection,
+      admin,
+      governanceTokenMint,
+      adminGovernanceTokenAccount,
+      admin,
+      1000,
+    );
+
+    const fundRewards = (treasurer: Keypair, from: PublicKey) =>
+      program.methods
+        .fundRewards(new anchor.BN(1000), new anchor.BN(2))
+        .accounts({
+          globalState,
+          role: null,
+          governanceTokenMint,
+          treasurerGovernanceTokenAccount: from,
+          rewardTokenAccount,
+          treasurer: treasurer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([treasurer])
+        .rpc();
+
+    try {
+      await fundRewards(user, userGovernanceTokenAccount);
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+
+    await fundRewards(admin, adminGovernanceTokenAccount);
+    await new Promise((resolve) => setTimeout(resolve, 3 * 1000));
+
+    const { amount: balanceBefore } = (
+      await provider.connection.getTokenAccountBalance(
+        userGovernanceTokenAccount,
+      )
+    ).value;
+
+    await program.methods
+      .claimStakingRewards()
+      .accounts({
+        globalState,
+        stakePosition,
+        governanceTokenMint,
+        ownerGovernanceTokenAccount: userGovernanceTokenAccount,
+        rewardTokenAccount,
+        owner: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    // The only staker earns the whole stream
+    const { amount: balanceAfter } = (
+      await provider.connection.getTokenAccountBalance(
+        userGovernanceTokenAccount,
+      )
+    ).value;
+    expect(Number(balanceAfter) - Number(balanceBefore)).to.equal(1000);
+
+    await program.methods
+      .unstakeStGovernance(new anchor.BN(100))
+      .accounts({
+        globalState,
+        stakePosition,
+        stGovernanceTokenMint,
+        ownerStGovernanceTokenAccount: userStGovernanceTokenAccount,
+        stakeTokenAccount,
+        owner: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const stakePositionAccount = await program.account.stakePosition.fetch(
+      stakePosition,
+    );
+    expect(stakePositionAccount.amount.toString()).to.equal("0");
+  });
+
+  it("Fails to vote after deadline", async () => {
+    // Wait for the deadline to pass
+    await new Promise((resolve) => setTimeout(resolve, 10 * 1000));
+
+    const amount = new anchor.BN(50);
+
+    try {
+      await program.methods
+        .vote(vaultId, amount, [], null)
+        .accounts({
+          globalState,
+          stGovernanceTokenMint,
+          voteTokenMint,
+          userStGovernanceTokenAccount,
+          userVoteTokenAccount,
+          vault,
+          userVault,
+          user: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+
+      // If we reach here, the test should fail
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Voting period has ended");
+    }
+  });
+
+  it("Sets project token", async () => {
+    projectTokenMint = await createMint(
+      provider.connection,
+      admin,
+      admin.publicKey,
+      null,
+      6,
+    );
+
+    const convertTime = Math.floor(Date.now() / 1000) + 10; // 30 seconds from now
+
+    await program.methods
+      .setProjectToken(projectTokenMint, new anchor.BN(convertTime))
+      .accounts({
+        globalState,
+        role: null,
+        vault,
+        owner: admin.publicKey,
+      })
+      .signers([admin])
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.projectTokenMint.toString()).to.equal(
+      projectTokenMint.toString(),
+    );
+    expect(vaultAccount.convertTime.toString()).to.equal(
+      convertTime.toString(),
+    );
+  });
+
+  it("Deposits project tokens", async () => {
+    const amount = new anchor.BN(1000);
+
+    // Create partnership project token account and mint some tokens
+    partnershipProjectTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      admin,
+      projectTokenMint,
+      partnership.publicKey,
+    ).then((account) => account.address);
+
+    await mintTo(
+      provider.connection,
+      admin,
+      projectTokenMint,
+      partnershipProjectTokenAccount,
+      admin,
+      2000,
+    );
+
+    vaultTokenAccount = await getAssociatedTokenAddressSync(
+      projectTokenMint,
+      vault,
+      true,
+    );
+
+    try {
+      await program.methods
+        .depositProjectTokens(amount)
+        .accounts({
+          globalState,
+          role: null,
+          vault,
+          projectTokenAccount: partnershipProjectTokenAccount,
+          vaultTokenAccount,
+          projectTokenMint,
+          projectAuthority: partnership.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([partnership])
+        .rpc();
+      expect.fail("Expected an error, but none was thrown");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+
+    const partnerRole = await grantRole(
+      "projectPartner",
+      partnership.publicKey,
+    );
+
+    await program.methods
+      .depositProjectTokens(amount)
+      .accounts({
+        globalState,
+        role: partnerRole,
+        vault,
+        projectTokenAccount: partnershipProjectTokenAccount,
+        vaultTokenAccount,
+        projectTokenMint,
+        projectAuthority: partnership.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([partnership])
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vault);
+    expect(vaultAccount.totalDeposited.toString()).to.equal("1000");
+  });
+
+  it("Claims project tokens", async () => {
+    await new Promise((resolve) => setTimeout(resolve, 10 * 1000)); // Wait for 30 seconds
+
+    userProjectTokenAccount = await getAssociatedTokenAddressSync(
+      projectTokenMint,
+      user.publicKey,
+    );
+
+    const claimQuote = await program.methods
+      .quoteClaim(vaultId)
+      .accounts({ vault, userVault, user: user.publicKey })
+      .view();
+    expect(claimQuote.claimAmount.toString()).to.equal("1000");
+
+    await program.methods
+      .claimProjectTokens(vaultId)
+      .accounts({
+        globalState,
         vault,
         voteTokenMint,
         projectTokenMint,